@@ -0,0 +1,787 @@
+use crate::file_types::{C_LIKE_SOURCE, SCRIPT_SOURCE};
+use crate::lexical_analysis::{normalize, AnalysisSettings, Lexer};
+use crate::stop_words::StopWords;
+use rust_stemmers::{Algorithm, Stemmer};
+use std::collections::HashMap;
+use std::ops::Range;
+use std::path::Path;
+use std::rc::Rc;
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+
+#[derive(Debug, PartialEq)]
+pub struct Term {
+    pub text: String,
+    // Byte range of the text it was made from
+    pub span: Range<usize>,
+    // Part of the term before it, like "2" of "1.2.3", indexed at the same position
+    pub part: bool,
+    // Another form of the whole term before it, like "resume" of "résumé", searched for with it
+    pub variant: bool,
+}
+
+impl Term {
+    pub fn new(text: impl Into<String>, span: Range<usize>) -> Self {
+        Self {
+            text: text.into(),
+            span,
+            part: false,
+            variant: false,
+        }
+    }
+
+    fn part(text: impl Into<String>, span: Range<usize>) -> Self {
+        Self {
+            part: true,
+            ..Self::new(text, span)
+        }
+    }
+}
+
+// Terms in the order they are read, made one at a time so a document is never held in memory
+// as a whole list of them
+pub type Terms<'t> = Box<dyn Iterator<Item = Term> + 't>;
+
+// Turns text into the terms that are indexed, or searched for
+pub trait Analyzer {
+    fn analyze<'t>(&'t self, text: &'t str) -> Terms<'t>;
+}
+
+// Splits text into raw tokens
+pub trait Tokenizer {
+    fn tokenize<'t>(&'t self, text: &'t str) -> Terms<'t>;
+}
+
+// Rewrites a token, None leaves it out
+pub trait Filter {
+    fn filter(&self, token: String) -> Option<String>;
+
+    // Another form of the token, indexed along with it
+    fn variant(&self, _token: &str) -> Option<String> {
+        None
+    }
+}
+
+// Words, numbers and single symbols, as split by the Lexer.
+// Special tokens are followed by their words and numbers, so "1.2.3" is also found by "2"
+#[derive(Default)]
+pub struct Words {
+    pub special_tokens: bool,
+}
+
+impl Tokenizer for Words {
+    fn tokenize<'t>(&'t self, text: &'t str) -> Terms<'t> {
+        Box::new(words(text, self.special_tokens))
+    }
+}
+
+// Special tokens are followed by their parts, as long as they have more than one
+fn words(text: &str, special_tokens: bool) -> impl Iterator<Item = Term> + '_ {
+    let mut lexer = Lexer::new(text).with_special_tokens(special_tokens);
+
+    std::iter::from_fn(move || lexer.next_token_span()).flat_map(move |(token, span)| {
+        let whole = Term::new(token, span.clone());
+        let split = special_tokens
+            && Lexer::new(token)
+                .filter(|part| part.starts_with(char::is_alphanumeric))
+                .nth(1)
+                .is_some();
+
+        let mut lexer = split.then(|| Lexer::new(token));
+        let parts = std::iter::from_fn(move || lexer.as_mut()?.next_token_span())
+            .filter(|(part, _)| part.starts_with(char::is_alphanumeric))
+            .map(move |(part, part_span)| {
+                let start = span.start + part_span.start;
+                Term::part(part, start..start + part.len())
+            });
+
+        std::iter::once(whole).chain(parts)
+    })
+}
+
+// Words, with runs of Chinese, Japanese and Korean characters split into overlapping
+// n-grams, as they are written without spaces. "東京都" becomes "東京" and "京都" for n = 2,
+// a query for any part of a run is then a phrase of its n-grams. The shorter grams are
+// parts of the n-gram they start in, or of the last one at the end of a run, so a query
+// shorter than n finds them too
+pub struct CjkNGrams {
+    pub n: usize,
+    pub words: Words,
+}
+
+impl Tokenizer for CjkNGrams {
+    fn tokenize<'t>(&'t self, text: &'t str) -> Terms<'t> {
+        Box::new(self.words.tokenize(text).flat_map(|term| {
+            // Words without CJK characters are kept as they are
+            let (whole, grams) = if term.text.chars().any(is_cjk) {
+                (None, self.grams(term))
+            } else {
+                (Some(term), Vec::new())
+            };
+            whole.into_iter().chain(grams)
+        }))
+    }
+}
+
+impl CjkNGrams {
+    fn grams(&self, term: Term) -> Vec<Term> {
+        let n = self.n.max(1);
+        let Term {
+            text: word,
+            span,
+            part,
+            ..
+        } = term;
+        let mut tokens = Vec::new();
+
+        // Byte offset of every character, and of the end of the word
+        let bounds = word
+            .char_indices()
+            .map(|(i, _)| i)
+            .chain([word.len()])
+            .collect::<Vec<_>>();
+        let chars = word.chars().collect::<Vec<_>>();
+        let mut start = 0;
+
+        while start < chars.len() {
+            let cjk = is_cjk(chars[start]);
+            let end = chars[start..]
+                .iter()
+                .position(|&c| is_cjk(c) != cjk)
+                .map_or(chars.len(), |length| start + length);
+
+            // A run shorter than n is kept whole, other text as it is
+            let grams = if cjk {
+                (start..start + (end - start).saturating_sub(n) + 1)
+                    .map(|gram| (gram, (gram + n).min(end)))
+                    .collect()
+            } else {
+                vec![(start, end)]
+            };
+
+            let last = grams.len().saturating_sub(1);
+            for (i, (gram_start, gram_end)) in grams.into_iter().enumerate() {
+                let text = &word[bounds[gram_start]..bounds[gram_end]];
+                tokens.push(Term {
+                    text: text.to_string(),
+                    span: span.start + bounds[gram_start]..span.start + bounds[gram_end],
+                    part,
+                    variant: false,
+                });
+
+                // Every shorter gram starting where the n-gram does, and after it at the
+                // end of the run, so a query shorter than n is found as a single term
+                if cjk {
+                    let starts_end = if i == last { gram_end } else { gram_start + 1 };
+                    for short_start in gram_start..starts_end {
+                        for short_end in short_start + 1..=gram_end {
+                            if (short_start, short_end) == (gram_start, gram_end) {
+                                continue;
+                            }
+                            let (short_start, short_end) = (bounds[short_start], bounds[short_end]);
+                            tokens.push(Term::part(
+                                &word[short_start..short_end],
+                                span.start + short_start..span.start + short_end,
+                            ));
+                        }
+                    }
+                }
+            }
+
+            start = end;
+        }
+
+        tokens
+    }
+}
+
+fn is_cjk(c: char) -> bool {
+    matches!(c,
+        '\u{1100}'..='\u{11FF}'       // Hangul Jamo
+        | '\u{3040}'..='\u{30FF}'     // Hiragana, Katakana
+        | '\u{3130}'..='\u{318F}'     // Hangul Compatibility Jamo
+        | '\u{31F0}'..='\u{31FF}'     // Katakana Phonetic Extensions
+        | '\u{3400}'..='\u{4DBF}'     // CJK Unified Ideographs Extension A
+        | '\u{4E00}'..='\u{9FFF}'     // CJK Unified Ideographs
+        | '\u{AC00}'..='\u{D7AF}'     // Hangul Syllables
+        | '\u{F900}'..='\u{FAFF}'     // CJK Compatibility Ideographs
+        | '\u{FF66}'..='\u{FF9F}'     // Halfwidth Katakana
+        | '\u{20000}'..='\u{2FA1F}'   // Supplementary Ideographic Planes
+    )
+}
+
+// Source code, identifiers are kept whole and followed by their words as parts,
+// "parseQuery", "parse_query" and "PARSE_QUERY" are all found by "parse" and "query"
+pub struct Code {
+    // Characters strings are quoted in
+    pub quotes: &'static [char],
+    // 'c' is a character and not a string, while 'a stays a Rust lifetime
+    pub char_literals: bool,
+    // Text of string literals is left out
+    pub skip_strings: bool,
+}
+
+impl Code {
+    pub fn c_like(skip_strings: bool) -> Self {
+        Self {
+            quotes: &['"'],
+            char_literals: true,
+            skip_strings,
+        }
+    }
+
+    pub fn script(skip_strings: bool) -> Self {
+        Self {
+            quotes: &['"', '\'', '`'],
+            char_literals: false,
+            skip_strings,
+        }
+    }
+
+    // Byte length of the string literal, or character literal, `text` starts with
+    fn string_end(&self, text: &str) -> Option<usize> {
+        let quote = text.chars().next()?;
+
+        if self.char_literals && quote == '\'' {
+            let mut chars = text.char_indices().skip(1);
+            let (_, c) = chars.next()?;
+            if c == '\\' {
+                chars.next()?;
+            }
+            return chars
+                .next()
+                .filter(|&(_, c)| c == '\'')
+                .map(|(end, _)| end + 1);
+        }
+
+        if !self.quotes.contains(&quote) {
+            return None;
+        }
+
+        // Unclosed strings run until the end of the line
+        let mut escaped = false;
+        for (i, c) in text.char_indices().skip(1) {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '\n' if quote != '`' => return Some(i),
+                c if c == quote => return Some(i + c.len_utf8()),
+                _ => {}
+            }
+        }
+
+        Some(text.len())
+    }
+}
+
+impl Tokenizer for Code {
+    fn tokenize<'t>(&'t self, text: &'t str) -> Terms<'t> {
+        let mut i = 0;
+        // Words of the last string, or parts of the last identifier
+        let mut pending: Terms<'t> = Box::new(std::iter::empty());
+
+        Box::new(std::iter::from_fn(move || loop {
+            if let Some(term) = pending.next() {
+                return Some(term);
+            }
+
+            let c = text[i..].chars().next()?;
+            let rest = &text[i..];
+            let start = i;
+
+            if c.is_whitespace() {
+                i += c.len_utf8();
+            } else if let Some(end) = self.string_end(rest) {
+                i += end;
+                if !self.skip_strings {
+                    let inner = &rest[c.len_utf8()..end];
+                    let inner = inner.strip_suffix(c).unwrap_or(inner);
+                    let offset = start + c.len_utf8();
+                    pending = Box::new(words(inner, false).map(move |term| {
+                        let span = offset + term.span.start..offset + term.span.end;
+                        Term::new(term.text, span)
+                    }));
+                }
+            } else if c.is_alphabetic() || c == '_' {
+                let length = rest
+                    .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+                    .unwrap_or(rest.len());
+                let identifier = &rest[..length];
+                i += length;
+
+                let words = sub_words(identifier);
+                if words.len() > 1 {
+                    pending = Box::new(words.into_iter().map(move |word| {
+                        let span = start + word.start..start + word.end;
+                        Term::part(&identifier[word], span)
+                    }));
+                }
+                return Some(Term::new(identifier, start..i));
+            } else {
+                // Numbers and symbols as the Lexer splits them
+                let (token, span) = Lexer::new(rest).next_token_span()?;
+                i += span.end;
+                return Some(Term::new(token, start + span.start..start + span.end));
+            }
+        }))
+    }
+}
+
+// Byte ranges of the words of an identifier, split at underscores and where the case changes
+fn sub_words(identifier: &str) -> Vec<Range<usize>> {
+    let mut words = Vec::new();
+    let mut start = None;
+    let mut chars = identifier.char_indices().peekable();
+    let mut previous: Option<char> = None;
+
+    while let Some((i, c)) = chars.next() {
+        if c == '_' {
+            if let Some(start) = start.take() {
+                words.push(start..i);
+            }
+            previous = None;
+            continue;
+        }
+
+        let next = chars.peek().map(|&(_, next)| next);
+        // "parseQuery" splits before "Q", "HTTPServer" before "S"
+        let boundary = match previous {
+            Some(previous) => {
+                (previous.is_lowercase() || previous.is_numeric()) && c.is_uppercase()
+                    || previous.is_uppercase()
+                        && c.is_uppercase()
+                        && next.is_some_and(char::is_lowercase)
+            }
+            None => false,
+        };
+
+        if boundary {
+            if let Some(start) = start.take() {
+                words.push(start..i);
+            }
+        }
+        start.get_or_insert(i);
+        previous = Some(c);
+    }
+
+    if let Some(start) = start {
+        words.push(start..identifier.len());
+    }
+
+    words
+}
+
+// Compatibility composition and case folding, see `normalize`
+pub struct Lowercase;
+
+impl Filter for Lowercase {
+    fn filter(&self, token: String) -> Option<String> {
+        Some(normalize(&token))
+    }
+}
+
+impl Filter for StopWords {
+    fn filter(&self, token: String) -> Option<String> {
+        (!self.contains(&token)).then_some(token)
+    }
+}
+
+// Keeps the token as it is, with the accents removed as a variant
+pub struct FoldDiacritics;
+
+impl Filter for FoldDiacritics {
+    fn filter(&self, token: String) -> Option<String> {
+        Some(token)
+    }
+
+    fn variant(&self, token: &str) -> Option<String> {
+        let folded = token
+            .nfd()
+            .filter(|c| !is_combining_mark(*c))
+            .nfc()
+            .collect::<String>();
+
+        (folded != token).then_some(folded)
+    }
+}
+
+pub struct Stemming(Stemmer);
+
+impl Stemming {
+    pub fn english() -> Self {
+        Self(Stemmer::create(Algorithm::English))
+    }
+}
+
+impl Filter for Stemming {
+    fn filter(&self, token: String) -> Option<String> {
+        Some(self.0.stem(&token).into_owned())
+    }
+}
+
+// A tokenizer followed by filters, applied in the order they were added
+pub struct Pipeline {
+    tokenizer: Box<dyn Tokenizer>,
+    filters: Vec<Box<dyn Filter>>,
+}
+
+impl Pipeline {
+    pub fn new(tokenizer: impl Tokenizer + 'static) -> Self {
+        Self {
+            tokenizer: Box::new(tokenizer),
+            filters: Vec::new(),
+        }
+    }
+
+    pub fn filter(mut self, filter: impl Filter + 'static) -> Self {
+        self.filters.push(Box::new(filter));
+        self
+    }
+}
+
+impl Analyzer for Pipeline {
+    fn analyze<'t>(&'t self, text: &'t str) -> Terms<'t> {
+        // Parts of a token that was left out have no position to take
+        let mut kept = false;
+
+        Box::new(self.tokenizer.tokenize(text).flat_map(move |term| {
+            let mut variants = Vec::new();
+            let filtered = if term.part && !kept {
+                None
+            } else {
+                apply(&self.filters, term.text, &mut variants)
+            };
+
+            if !term.part {
+                kept = filtered.is_some();
+            }
+            if let Some(text) = &filtered {
+                variants.sort();
+                variants.dedup();
+                variants.retain(|variant| variant != text);
+            } else {
+                variants.clear();
+            }
+
+            let span = term.span;
+            let whole = filtered.map(|text| Term {
+                text,
+                span: span.clone(),
+                part: term.part,
+                variant: false,
+            });
+            let variants = variants.into_iter().map(move |variant| Term {
+                text: variant,
+                span: span.clone(),
+                part: true,
+                variant: !term.part,
+            });
+            whole.into_iter().chain(variants)
+        }))
+    }
+}
+
+// `token` through the filters, the variants some of them add go through the rest
+fn apply(filters: &[Box<dyn Filter>], token: String, variants: &mut Vec<String>) -> Option<String> {
+    let Some((filter, rest)) = filters.split_first() else {
+        return Some(token);
+    };
+
+    if let Some(variant) = filter.variant(&token) {
+        if let Some(variant) = apply(rest, variant, variants) {
+            variants.push(variant);
+        }
+    }
+
+    let token = filter.filter(token)?;
+    apply(rest, token, variants)
+}
+
+impl AnalysisSettings {
+    pub fn analyzer(&self) -> Pipeline {
+        let words = Words {
+            special_tokens: self.special_tokens,
+        };
+
+        match self.cjk_ngrams {
+            Some(n) => self.pipeline(CjkNGrams { n, words }),
+            None => self.pipeline(words),
+        }
+    }
+
+    // The tokenizer followed by the filters of the settings
+    fn pipeline(&self, tokenizer: impl Tokenizer + 'static) -> Pipeline {
+        let mut pipeline = Pipeline::new(tokenizer)
+            .filter(Lowercase)
+            .filter(self.stop_words.clone());
+
+        if self.fold_diacritics {
+            pipeline = pipeline.filter(FoldDiacritics);
+        }
+
+        if self.stemming {
+            pipeline = pipeline.filter(Stemming::english());
+        }
+
+        pipeline
+    }
+}
+
+// The analyzer of every file type, built once from the settings of the index.
+// Queries are analyzed the way every file type is, so they find the terms of each one
+pub struct Analyzers {
+    default: Rc<dyn Analyzer>,
+    extensions: HashMap<String, Rc<dyn Analyzer>>,
+    // Every registered analyzer once, queries are analyzed with each of them
+    registered: Vec<Rc<dyn Analyzer>>,
+}
+
+impl Analyzers {
+    pub fn new(settings: &AnalysisSettings) -> Self {
+        let mut analyzers = Self {
+            default: Rc::new(settings.analyzer()),
+            extensions: HashMap::new(),
+            registered: Vec::new(),
+        };
+
+        let skip_strings = settings.skip_strings;
+        analyzers.register(C_LIKE_SOURCE, settings.pipeline(Code::c_like(skip_strings)));
+        analyzers.register(SCRIPT_SOURCE, settings.pipeline(Code::script(skip_strings)));
+
+        analyzers
+    }
+
+    // File types with their own tokenizer or filters, like source code
+    pub fn register(&mut self, extensions: &[&str], analyzer: impl Analyzer + 'static) {
+        let analyzer: Rc<dyn Analyzer> = Rc::new(analyzer);
+
+        for extension in extensions {
+            self.extensions
+                .insert(extension.to_string(), Rc::clone(&analyzer));
+        }
+        self.registered.push(analyzer);
+    }
+
+    // The default analyzer first, then every registered one, so a query finds the terms
+    // of any file type. See `Query::parse`
+    pub fn query(&self) -> Vec<&dyn Analyzer> {
+        [&self.default]
+            .into_iter()
+            .chain(&self.registered)
+            .map(Rc::as_ref)
+            .collect()
+    }
+
+    pub fn for_path(&self, path: &Path) -> &dyn Analyzer {
+        path.extension()
+            .and_then(|extension| self.extensions.get(extension.to_str()?))
+            .unwrap_or(&self.default)
+            .as_ref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Analyzer, Analyzers, CjkNGrams, Code, Filter, Pipeline, Terms, Tokenizer, Words};
+    use crate::lexical_analysis::AnalysisSettings;
+    use std::ops::Range;
+    use std::path::Path;
+
+    struct Reverse;
+
+    impl Filter for Reverse {
+        fn filter(&self, token: String) -> Option<String> {
+            Some(token.chars().rev().collect())
+        }
+    }
+
+    fn terms(analyzer: &dyn Analyzer, text: &str) -> Vec<String> {
+        analyzer.analyze(text).map(|term| term.text).collect()
+    }
+
+    fn spans(terms: Terms) -> Vec<(String, Range<usize>, bool)> {
+        terms
+            .map(|term| (term.text, term.span, term.part))
+            .collect()
+    }
+
+    #[test]
+    fn analyzers_per_file_type() {
+        let settings = AnalysisSettings {
+            stemming: true,
+            stop_words: "punctuation,english".parse().unwrap(),
+            ..AnalysisSettings::default()
+        };
+        let mut analyzers = Analyzers::new(&settings);
+        analyzers.register(&["rev"], Pipeline::new(Words::default()).filter(Reverse));
+
+        assert_eq!(
+            terms(analyzers.query()[0], "The Indexed, Files"),
+            vec!["index", "file"]
+        );
+        assert_eq!(
+            terms(analyzers.for_path(Path::new("notes.txt")), "The Indexed"),
+            vec!["index"]
+        );
+        assert_eq!(
+            terms(analyzers.for_path(Path::new("notes.rev")), "The Indexed"),
+            vec!["ehT", "dexednI"]
+        );
+        assert_eq!(
+            analyzers.query()[0].analyze("a  rust").next().unwrap().span,
+            3..7
+        );
+    }
+
+    #[test]
+    fn cjk_ngrams() {
+        let tokenizer = CjkNGrams {
+            n: 2,
+            words: Words::default(),
+        };
+        assert_eq!(
+            spans(tokenizer.tokenize("東京都庁 in Tokyo、日本")),
+            vec![
+                ("東京".to_string(), 0..6, false),
+                ("東".to_string(), 0..3, true),
+                ("京都".to_string(), 3..9, false),
+                ("京".to_string(), 3..6, true),
+                ("都庁".to_string(), 6..12, false),
+                ("都".to_string(), 6..9, true),
+                ("庁".to_string(), 9..12, true),
+                ("in".to_string(), 13..15, false),
+                ("Tokyo".to_string(), 16..21, false),
+                ("、".to_string(), 21..24, false),
+                ("日本".to_string(), 24..30, false),
+                ("日".to_string(), 24..27, true),
+                ("本".to_string(), 27..30, true),
+            ]
+        );
+
+        // A query shorter than n is a term of its own, found among the parts of a longer run
+        assert_eq!(
+            spans(tokenizer.tokenize("京")),
+            vec![("京".to_string(), 0..3, false)]
+        );
+
+        // Latin text is split as before, a short run stays whole
+        let tokenizer = CjkNGrams {
+            n: 3,
+            words: Words::default(),
+        };
+        assert_eq!(
+            spans(tokenizer.tokenize("rust東京")),
+            vec![
+                ("rust".to_string(), 0..4, false),
+                ("東京".to_string(), 4..10, false),
+                ("東".to_string(), 4..7, true),
+                ("京".to_string(), 7..10, true),
+            ]
+        );
+
+        // Every gram shorter than n is indexed, so a 2-character query is found as a term
+        assert_eq!(
+            spans(tokenizer.tokenize("東京都庁")),
+            vec![
+                ("東京都".to_string(), 0..9, false),
+                ("東".to_string(), 0..3, true),
+                ("東京".to_string(), 0..6, true),
+                ("京都庁".to_string(), 3..12, false),
+                ("京".to_string(), 3..6, true),
+                ("京都".to_string(), 3..9, true),
+                ("都".to_string(), 6..9, true),
+                ("都庁".to_string(), 6..12, true),
+                ("庁".to_string(), 9..12, true),
+            ]
+        );
+        let indexed = tokenizer
+            .tokenize("東京都庁")
+            .map(|term| term.text)
+            .collect::<Vec<_>>();
+        for query in ["東京", "京都", "都庁"] {
+            let terms = tokenizer.tokenize(query).collect::<Vec<_>>();
+            assert!(!terms[0].part && indexed.contains(&terms[0].text));
+        }
+    }
+
+    #[test]
+    fn special_tokens() {
+        let words = Words {
+            special_tokens: true,
+        };
+        let tokens = words
+            .tokenize(
+                "v 1.2.3, 10.25 and 192.168.0.1. Mail ann.lee@example.com or see https://example.com/a_b. x86_64",
+            )
+            .collect::<Vec<_>>();
+        let whole = tokens
+            .iter()
+            .filter(|token| !token.part)
+            .map(|token| token.text.as_str())
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            whole,
+            vec![
+                "v",
+                "1.2.3",
+                ",",
+                "10.25",
+                "and",
+                "192.168.0.1",
+                ".",
+                "Mail",
+                "ann.lee@example.com",
+                "or",
+                "see",
+                "https://example.com/a_b",
+                ".",
+                "x86_64"
+            ]
+        );
+        assert_eq!(
+            spans(words.tokenize("1.2.3")),
+            vec![
+                ("1.2.3".to_string(), 0..5, false),
+                ("1".to_string(), 0..1, true),
+                ("2".to_string(), 2..3, true),
+                ("3".to_string(), 4..5, true),
+            ]
+        );
+
+        // Parts of a left out token are left out too
+        let pipeline = Pipeline::new(words).filter(Reverse).filter(Reject("3.2.1"));
+        assert!(pipeline.analyze("1.2.3").next().is_none());
+    }
+
+    #[test]
+    fn source_code() {
+        let source =
+            "fn parseQuery<'a>(c: char) { if c == '\"' { HTTP_SERVER.log(\"bad query\") } }";
+        let terms = Code::c_like(false).tokenize(source).collect::<Vec<_>>();
+        let texts = |part| {
+            terms
+                .iter()
+                .filter(|term| term.part == part)
+                .map(|term| term.text.as_str())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(texts(true), vec!["parse", "Query", "HTTP", "SERVER"]);
+        assert!(texts(false).contains(&"parseQuery"));
+        assert!(texts(false).contains(&"HTTP_SERVER"));
+        assert!(texts(false).contains(&"query"));
+
+        let terms = Code::c_like(true).tokenize(source).collect::<Vec<_>>();
+        assert!(!terms.iter().any(|term| term.text == "bad"));
+        assert!(terms.iter().any(|term| term.text == "log"));
+    }
+
+    struct Reject(&'static str);
+
+    impl Filter for Reject {
+        fn filter(&self, token: String) -> Option<String> {
+            (token != self.0).then_some(token)
+        }
+    }
+}
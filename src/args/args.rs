@@ -2,7 +2,6 @@
 use crate::args::osstr_ext;
 pub use osstr_ext::OsStrExt;
 use std::ffi::{OsStr, OsString};
-pub use std::io::SeekFrom;
 
 #[derive(Default, Clone, Debug, PartialEq, Eq)]
 pub struct RawArgs {
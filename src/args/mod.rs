@@ -1,8 +1,12 @@
+#[allow(clippy::module_inception)]
 mod args;
 mod osstr_ext;
 
 use crate::io_control::{IOControl, LogLevel};
-use crate::serve::Serve;
+use crate::lexical_analysis::AnalysisSettings;
+use crate::ranking::{FieldBoosts, Ranker, Scoring};
+use crate::serve::{Engine, Serve};
+use crate::stop_words::StopWords;
 use osstr_ext::OsStrExt;
 use std::ffi::OsString;
 use std::path::PathBuf;
@@ -21,6 +25,17 @@ impl Args {
             address: None,
             json: None,
             progress: false,
+            ranker: Ranker::default(),
+            k1: None,
+            b: None,
+            boosts: FieldBoosts::default(),
+            proximity: false,
+            stemming: false,
+            stop_words: StopWords::default(),
+            cjk_ngrams: None,
+            special_tokens: false,
+            skip_strings: false,
+            fold_diacritics: false,
         };
 
         let mut args = Args {
@@ -73,8 +88,7 @@ impl Args {
         let mut cursor = raw.cursor();
 
         while let Some(arg) = raw.next(&mut cursor) {
-            if arg.is_escape() {
-            } else if arg.is_stdio() {
+            if arg.is_escape() || arg.is_stdio() {
             } else if let Some((long, value)) = arg.to_long() {
                 match long {
                     Ok("help") => {
@@ -114,6 +128,133 @@ impl Args {
                             self.options.progress = true;
                         };
                     }
+                    Ok("proximity") => {
+                        if let Some(val) = value {
+                            if val == "true" {
+                                self.options.proximity = true;
+                            } else if val == "false" {
+                                self.options.proximity = false;
+                            } else {
+                                ArgLogging::error_log(format!(
+                                    "{:?} not a valid value for {:?}",
+                                    val,
+                                    long.unwrap()
+                                ));
+                            }
+                        } else {
+                            self.options.proximity = true;
+                        };
+                    }
+                    Ok("stemming") => {
+                        if let Some(val) = value {
+                            if val == "true" {
+                                self.options.stemming = true;
+                            } else if val == "false" {
+                                self.options.stemming = false;
+                            } else {
+                                ArgLogging::error_log(format!(
+                                    "{:?} not a valid value for {:?}",
+                                    val,
+                                    long.unwrap()
+                                ));
+                            }
+                        } else {
+                            self.options.stemming = true;
+                        };
+                    }
+                    Ok("special-tokens") => {
+                        if let Some(val) = value {
+                            if val == "true" {
+                                self.options.special_tokens = true;
+                            } else if val == "false" {
+                                self.options.special_tokens = false;
+                            } else {
+                                ArgLogging::error_log(format!(
+                                    "{:?} not a valid value for {:?}",
+                                    val,
+                                    long.unwrap()
+                                ));
+                            }
+                        } else {
+                            self.options.special_tokens = true;
+                        };
+                    }
+                    Ok("skip-strings") => {
+                        if let Some(val) = value {
+                            if val == "true" {
+                                self.options.skip_strings = true;
+                            } else if val == "false" {
+                                self.options.skip_strings = false;
+                            } else {
+                                ArgLogging::error_log(format!(
+                                    "{:?} not a valid value for {:?}",
+                                    val,
+                                    long.unwrap()
+                                ));
+                            }
+                        } else {
+                            self.options.skip_strings = true;
+                        };
+                    }
+                    Ok("fold-diacritics") => {
+                        if let Some(val) = value {
+                            if val == "true" {
+                                self.options.fold_diacritics = true;
+                            } else if val == "false" {
+                                self.options.fold_diacritics = false;
+                            } else {
+                                ArgLogging::error_log(format!(
+                                    "{:?} not a valid value for {:?}",
+                                    val,
+                                    long.unwrap()
+                                ));
+                            }
+                        } else {
+                            self.options.fold_diacritics = true;
+                        };
+                    }
+                    Ok("stop-words") => {
+                        if let Some(val) = value {
+                            match val.to_str().unwrap_or_default().parse::<StopWords>() {
+                                Ok(stop_words) => {
+                                    self.options.stop_words.punctuation = stop_words.punctuation;
+                                    self.options.stop_words.english = stop_words.english;
+                                }
+                                Err(err) => ArgLogging::error_log(err),
+                            }
+                        } else {
+                            self.options.stop_words.punctuation = true;
+                            self.options.stop_words.english = true;
+                        };
+                    }
+                    Ok("stop-words-file") => {
+                        if let Some(val) = value {
+                            if let Err(err) = self.options.stop_words.read_file(val) {
+                                ArgLogging::error_log(format!(
+                                    "Cannot read the stop words {:?}: {err}",
+                                    val
+                                ));
+                            }
+                        } else {
+                            ArgLogging::error_log(format!(
+                                "Provide a value for {:?}",
+                                long.unwrap()
+                            ));
+                        };
+                    }
+                    Ok("cjk-ngrams") => {
+                        if let Some(val) = value {
+                            match val.to_str().unwrap_or_default().parse::<usize>() {
+                                Ok(n) if n > 0 => self.options.cjk_ngrams = Some(n),
+                                _ => ArgLogging::error_log(format!(
+                                    "Provide a positive number for {:?}",
+                                    long.unwrap()
+                                )),
+                            }
+                        } else {
+                            self.options.cjk_ngrams = Some(default::CJK_NGRAMS);
+                        };
+                    }
                     Ok("json") => {
                         if let Some(val) = value {
                             self.options.json = Some(val.to_os_string());
@@ -134,6 +275,48 @@ impl Args {
                             ));
                         };
                     }
+                    Ok("ranker") => {
+                        if let Some(val) = value {
+                            match val.to_str().unwrap_or_default().parse::<Ranker>() {
+                                Ok(ranker) => self.options.ranker = ranker,
+                                Err(err) => ArgLogging::error_log(err),
+                            }
+                        } else {
+                            ArgLogging::error_log(format!(
+                                "Provide a value for {:?}",
+                                long.unwrap()
+                            ));
+                        };
+                    }
+                    Ok("boost") => {
+                        if let Some(val) = value {
+                            match val.to_str().unwrap_or_default().parse::<FieldBoosts>() {
+                                Ok(boosts) => self.options.boosts = boosts,
+                                Err(err) => ArgLogging::error_log(err),
+                            }
+                        } else {
+                            ArgLogging::error_log(format!(
+                                "Provide a value for {:?}",
+                                long.unwrap()
+                            ));
+                        };
+                    }
+                    Ok("k1") | Ok("b") => {
+                        let parsed = value.and_then(|val| val.to_str()?.parse::<f64>().ok());
+
+                        if let Some(val) = parsed {
+                            if long == Ok("k1") {
+                                self.options.k1 = Some(val);
+                            } else {
+                                self.options.b = Some(val);
+                            }
+                        } else {
+                            ArgLogging::error_log(format!(
+                                "Provide a number for {:?}",
+                                long.unwrap()
+                            ));
+                        };
+                    }
                     _ => {
                         ArgLogging::error_log(format!("{:?} not a valid option", long));
                     }
@@ -150,6 +333,9 @@ impl Args {
                         Ok('p') => {
                             self.options.progress = true;
                         }
+                        Ok('s') => {
+                            self.options.stemming = true;
+                        }
                         _ => {
                             println!("Developing On Going");
                         }
@@ -161,21 +347,27 @@ impl Args {
 
     pub fn implication(self) {
         let json_path = self.options.json_path();
+        let scoring = self.options.scoring();
 
         match self.command.unwrap() {
             /* Indexing */
             Command::Index(dir_entry) => {
                 if let Some(folder_path) = dir_entry.to_str() {
-                    LogLevel::SIGNAL(format!("Indexing...   {}", folder_path).to_string()).show();
+                    LogLevel::SIGNAL(format!("Indexing...   {}", folder_path)).show();
                     let entry = PathBuf::from(folder_path);
 
-                    let io_control =
-                        IOControl::new(entry, &json_path, self.options.deep, self.options.progress);
+                    let io_control = IOControl::new(
+                        entry,
+                        json_path,
+                        self.options.deep,
+                        self.options.progress,
+                        self.options.analysis_settings(),
+                    );
                     if let Err(err) = io_control.check_file_type() {
                         println!("{:?}", err);
                     }
                 } else {
-                    ArgLogging::error_log(format!("Provide a valid argumentfor path"));
+                    ArgLogging::error_log("Provide a valid argumentfor path".to_string());
                 };
             }
             /* Serving */
@@ -183,6 +375,14 @@ impl Args {
                 LogLevel::SIGNAL("Serving...".to_string()).show();
                 let address = self.options.ip_address();
 
+                let engine = match Engine::new(json_path, scoring) {
+                    Ok(engine) => engine,
+                    Err(err) => {
+                        LogLevel::ERROR(format!("Cannot load the index {json_path}: {err}")).show();
+                        return;
+                    }
+                };
+
                 let server = Server::http(address).unwrap();
                 println!("➜  Local:   http://{}", address);
 
@@ -196,7 +396,7 @@ impl Args {
                     };
 
                     let serve = Serve::new(request);
-                    serve.handle_connection(json_path).unwrap();
+                    serve.handle_connection(&engine).unwrap();
                 }
             }
         }
@@ -213,6 +413,17 @@ struct Options {
     address: Option<OsString>,
     json: Option<OsString>,
     progress: bool,
+    ranker: Ranker,
+    k1: Option<f64>,
+    b: Option<f64>,
+    boosts: FieldBoosts,
+    proximity: bool,
+    stemming: bool,
+    stop_words: StopWords,
+    cjk_ngrams: Option<usize>,
+    special_tokens: bool,
+    skip_strings: bool,
+    fold_diacritics: bool,
 }
 
 impl Options {
@@ -236,6 +447,25 @@ impl Options {
 
         json_path
     }
+
+    fn analysis_settings(&self) -> AnalysisSettings {
+        AnalysisSettings {
+            stemming: self.stemming,
+            stop_words: self.stop_words.clone(),
+            cjk_ngrams: self.cjk_ngrams,
+            special_tokens: self.special_tokens,
+            skip_strings: self.skip_strings,
+            fold_diacritics: self.fold_diacritics,
+        }
+    }
+
+    fn scoring(&self) -> Scoring {
+        Scoring {
+            ranker: self.ranker.tune(self.k1, self.b),
+            boosts: self.boosts,
+            proximity: self.proximity,
+        }
+    }
 }
 
 // ArgLogging::CommandError.error_logging("Command Not Found".to_string());
@@ -265,13 +495,25 @@ impl ArgLogging {
 
     valid:
         index [file | folder] --json=<json_file.json>
-        serve --json=<json_file>
+        serve --json=<json_file> --ranker=<tfidf | bm25>
 
     options:
         --json=<json_file>: JSON file to parse and get the data
         --address=<valid_ip_address>: Give an address to expose
         --deep=[true | false] | --deep | -d: Recursive the folder and try to get all data
         --progress=[true | false] | --progress | -p: Show the process
+        --stemming=[true | false] | --stemming | -s: Index the English stem of every word, indexing matches index
+        --stop-words=[punctuation,english] | --stop-words: Leave punctuation and common English words out of the index
+        --stop-words-file=<file>: Leave the words of the file out of the index, one or more per line
+        --fold-diacritics=[true | false] | --fold-diacritics: Index words without their accents too, resume finds résumé
+        --special-tokens=[true | false] | --special-tokens: Index versions, decimals, IPs, URLs and emails as one word, and their parts
+        --skip-strings=[true | false] | --skip-strings: Leave the text of string literals in source code out of the index
+        --cjk-ngrams=<number> | --cjk-ngrams: Split Chinese, Japanese and Korean text into character n-grams (default: 2)
+        --ranker=[tfidf | bm25]: Ranking algorithm for the search results (default: tfidf)
+        --k1=<number>: BM25 term frequency saturation (default: 1.2)
+        --b=<number>: BM25 document length normalization (default: 0.75)
+        --proximity=[true | false] | --proximity: Rank documents with the query terms close together higher
+        --boost=<field>:<number>,..: Weight of the title, heading, body, path, description, keywords, alt, tooltip and link fields (default: title:3,heading:2,path:2,description:2,keywords:2, others 1)
         "
         );
 
@@ -281,6 +523,7 @@ impl ArgLogging {
 }
 
 mod default {
-    pub(crate) const JSON_PATH: &'static str = "./index.json";
-    pub(crate) const ADDRESS: &'static str = "127.0.0.1:1919";
+    pub(crate) const JSON_PATH: &str = "./index.json";
+    pub(crate) const ADDRESS: &str = "127.0.0.1:1919";
+    pub(crate) const CJK_NGRAMS: usize = 2;
 }
@@ -2,10 +2,13 @@ use std::ffi::OsStr;
 
 pub trait OsStrExt: private::Sealed {
     fn try_str(&self) -> Result<&str, std::str::Utf8Error>;
+    // Kept as in clap_lex, the argument parser does not use it
+    #[allow(dead_code)]
     fn contains(&self, needle: &str) -> bool;
     fn find(&self, needle: &str) -> Option<usize>;
     fn strip_prefix(&self, prefix: &str) -> Option<&OsStr>;
     fn starts_with(&self, prefix: &str) -> bool;
+    #[allow(dead_code)]
     fn split<'s, 'n>(&'s self, needle: &'n str) -> Split<'s, 'n>;
     fn split_once(&self, needle: &'_ str) -> Option<(&OsStr, &OsStr)>;
 }
@@ -106,6 +109,8 @@ unsafe fn to_os_str_unchecked(s: &[u8]) -> &OsStr {
     std::mem::transmute(s)
 }
 
+// Returned by `split` only
+#[allow(dead_code)]
 pub struct Split<'s, 'n> {
     haystack: Option<&'s OsStr>,
     needle: &'n str,
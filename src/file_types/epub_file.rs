@@ -0,0 +1,131 @@
+use crate::file_types::xml_file::{attribute, elements, read_xml, XHTML};
+use crate::file_types::Content;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{Error, ErrorKind, Read, Result as IOResult};
+use std::path::Path;
+use zip::ZipArchive;
+
+//  Possible Errors ->
+//      File Open: NotFound, Permission Denied
+//      Unzip, missing package document, no chapter with text: InvalidData
+
+// Chapters of the spine in reading order, named by their id in the manifest
+pub fn read_epub_file<P: AsRef<Path>>(file_path: P) -> IOResult<Content> {
+    let mut archive = ZipArchive::new(File::open(file_path)?)?;
+
+    // The container points at the package document, which lists the chapters
+    let package_path = elements(archive.by_name("META-INF/container.xml")?, "rootfile")
+        .iter()
+        .find_map(|attributes| attribute(attributes, "full-path").map(str::to_string))
+        .ok_or_else(|| Error::new(ErrorKind::InvalidData, "the EPUB has no package document"))?;
+
+    let mut package = String::new();
+    archive
+        .by_name(&package_path)?
+        .read_to_string(&mut package)?;
+
+    // Chapters are found relative to the package document
+    let base = package_path.rsplit_once('/').map_or("", |(base, _)| base);
+    let manifest = elements(package.as_bytes(), "item")
+        .into_iter()
+        .filter_map(|attributes| {
+            let id = attribute(&attributes, "id")?.to_string();
+            let href = resolve(base, attribute(&attributes, "href")?);
+            Some((id, href))
+        })
+        .collect::<HashMap<_, _>>();
+
+    let mut content = Content::new();
+    for itemref in elements(package.as_bytes(), "itemref") {
+        let Some(id) = attribute(&itemref, "idref") else {
+            continue;
+        };
+        let Some(part) = manifest.get(id).and_then(|href| archive.by_name(href).ok()) else {
+            continue;
+        };
+
+        // Cover pages and other chapters without text are left out
+        let chapter = read_xml(part, &XHTML);
+        if !chapter.sections().is_empty() {
+            content.push_chapter(id, chapter);
+        }
+    }
+
+    if content.chapters().is_empty() {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            "the EPUB has no chapter with text",
+        ));
+    }
+
+    Ok(content)
+}
+
+// Path inside the archive of a link relative to the `base` folder
+fn resolve(base: &str, href: &str) -> String {
+    let href = href.split('#').next().unwrap_or_default();
+
+    let mut segments = base
+        .split('/')
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<_>>();
+    for segment in href.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                segments.pop();
+            }
+            segment => segments.push(segment),
+        }
+    }
+
+    segments.join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{read_epub_file, resolve};
+    use crate::file_types::Field;
+
+    #[test]
+    fn chapters_in_reading_order() {
+        let content = read_epub_file("files/book.epub").unwrap();
+
+        let chapters = content
+            .chapters()
+            .iter()
+            .map(|(name, chapter)| {
+                let sections = chapter.texts();
+                (name.as_str(), sections)
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            chapters,
+            vec![
+                (
+                    "chapter1",
+                    vec![
+                        (Field::Heading, "The Index"),
+                        (Field::Body, "Every word points to its documents."),
+                    ]
+                ),
+                (
+                    "chapter2",
+                    vec![
+                        (Field::Heading, "The Query"),
+                        (Field::Body, "Terms are looked up and ranked."),
+                    ]
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn links_are_relative_to_the_package() {
+        assert_eq!(resolve("OEBPS", "text/ch1.xhtml"), "OEBPS/text/ch1.xhtml");
+        assert_eq!(resolve("OEBPS/text", "../ch1.xhtml#top"), "OEBPS/ch1.xhtml");
+        assert_eq!(resolve("", "ch1.xhtml"), "ch1.xhtml");
+    }
+}
@@ -0,0 +1,245 @@
+use crate::file_types::{Content, Field};
+use ego_tree::iter::Edge;
+use scraper::node::Element;
+use scraper::{Html, Node};
+use std::fs;
+use std::io::Result as IOResult;
+use std::ops::Range;
+use std::path::Path;
+
+//  Possible Errors ->
+//      File Open: NotFound, Permission Denied
+
+// Elements whose text is code or markup, not part of the page
+const SKIPPED: &[&str] = &["script", "style", "noscript", "template"];
+// Elements inside a line of text, they do not split a word
+const INLINE: &[&str] = &[
+    "a", "abbr", "b", "bdi", "bdo", "cite", "code", "data", "dfn", "em", "font", "i", "img", "kbd",
+    "mark", "q", "s", "samp", "small", "span", "strong", "sub", "sup", "time", "u", "var", "wbr",
+];
+
+// Parsed the way browsers do, so unclosed tags, HTML entities and stray markup are tolerated
+pub fn read_html_file<P: AsRef<Path>>(file_path: P) -> IOResult<Content> {
+    let source = fs::read(file_path)?;
+    let document = Html::parse_document(&String::from_utf8_lossy(&source));
+
+    let mut content = Content::new();
+    // Field of every open element, None inside the skipped ones
+    let mut open: Vec<Option<Field>> = Vec::new();
+    let mut block = Block::default();
+    // Depth of `pre` elements, whitespace in them is kept as it is
+    let mut preformatted = 0;
+
+    for edge in document.root_element().traverse() {
+        let current = open.last().copied().unwrap_or(Some(Field::Body));
+
+        match edge {
+            Edge::Open(node) => match node.value() {
+                Node::Text(text) if current.is_some() && preformatted > 0 => {
+                    block.text.push_str(text)
+                }
+                // Runs of whitespace are a single space, as browsers show them
+                Node::Text(text) if current.is_some() => {
+                    for (i, word) in text.split_ascii_whitespace().enumerate() {
+                        if i > 0 || text.starts_with(|c: char| c.is_ascii_whitespace()) {
+                            block.text.push(' ');
+                        }
+                        block.text.push_str(word);
+                    }
+                    if text.ends_with(|c: char| c.is_ascii_whitespace()) {
+                        block.text.push(' ');
+                    }
+                }
+                Node::Element(element) => {
+                    let name = element.name();
+                    if name == "pre" {
+                        preformatted += 1;
+                    }
+
+                    let field = match current {
+                        Some(_) if SKIPPED.contains(&name) => None,
+                        Some(field) => Some(field_of(name).unwrap_or(field)),
+                        None => None,
+                    };
+
+                    if let Some(current) = current {
+                        if field != Some(current) || !INLINE.contains(&name) {
+                            block.flush(&mut content, current);
+                        }
+                    }
+                    if field.is_some() {
+                        block.attributes.extend(attribute_texts(element));
+                    }
+                    // Links in a heading or the title are part of it
+                    if name == "a" && field == Some(Field::Body) {
+                        block.link_start = Some(block.text.len());
+                    }
+                    open.push(field);
+                }
+                _ => {}
+            },
+            Edge::Close(node) => {
+                let Node::Element(element) = node.value() else {
+                    continue;
+                };
+
+                if element.name() == "pre" {
+                    preformatted -= 1;
+                }
+                if element.name() == "a" {
+                    if let Some(start) = block.link_start.take() {
+                        block.links.push(start..block.text.len());
+                    }
+                }
+
+                let field = open.pop().flatten();
+                let parent = open.last().copied().unwrap_or(Some(Field::Body));
+                if let Some(field) = field {
+                    if Some(field) != parent || !INLINE.contains(&element.name()) {
+                        block.flush(&mut content, field);
+                    }
+                }
+            }
+        }
+    }
+
+    block.flush(&mut content, Field::Body);
+    Ok(content)
+}
+
+// Text of the block being read, with the links in it and the attribute text of its elements
+#[derive(Default)]
+struct Block {
+    text: String,
+    // Byte ranges of the links in `text`
+    links: Vec<Range<usize>>,
+    // Start of the link being read
+    link_start: Option<usize>,
+    attributes: Vec<(Field, String)>,
+}
+
+impl Block {
+    // Links weigh as their field but stay in the text, attribute text goes after it,
+    // so neither cuts a phrase of the block
+    fn flush(&mut self, content: &mut Content, field: Field) {
+        // A link running into the next block goes on in it
+        if let Some(start) = self.link_start {
+            self.links.push(start..self.text.len());
+            self.link_start = Some(0);
+        }
+
+        let text = self.text.trim();
+        if !text.is_empty() {
+            let offset = self.text.len() - self.text.trim_start().len();
+            let marks = self
+                .links
+                .iter()
+                .map(|link| {
+                    let start = link.start.saturating_sub(offset);
+                    let end = link.end.saturating_sub(offset).min(text.len());
+                    (start..end, Field::Link)
+                })
+                .filter(|(range, _)| !range.is_empty())
+                .collect::<Vec<_>>();
+            content.push_marked(field, text, &marks);
+        }
+
+        for (field, text) in self.attributes.drain(..) {
+            if !text.trim().is_empty() {
+                content.push(field, text.trim());
+            }
+        }
+        self.text.clear();
+        self.links.clear();
+    }
+}
+
+// Field of the text inside an element, by its local name
+pub(super) fn field_of(element: &str) -> Option<Field> {
+    match element.to_ascii_lowercase().as_str() {
+        "title" => Some(Field::Title),
+        "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => Some(Field::Heading),
+        _ => None,
+    }
+}
+
+// Meta description and keywords, `alt` text and `title` attributes of an element
+fn attribute_texts(element: &Element) -> Vec<(Field, String)> {
+    let mut texts = Vec::new();
+
+    if element.name() == "meta" {
+        let name = element.attr("name").unwrap_or_default();
+        let field = if name.eq_ignore_ascii_case("description") {
+            Some(Field::Description)
+        } else if name.eq_ignore_ascii_case("keywords") {
+            Some(Field::Keywords)
+        } else {
+            None
+        };
+
+        if let (Some(field), Some(text)) = (field, element.attr("content")) {
+            texts.push((field, text.to_string()));
+        }
+    }
+    if let Some(alt) = element.attr("alt") {
+        texts.push((Field::Alt, alt.to_string()));
+    }
+    if let Some(title) = element.attr("title") {
+        texts.push((Field::Tooltip, title.to_string()));
+    }
+
+    texts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::read_html_file;
+    use crate::file_types::Field;
+
+    #[test]
+    fn malformed_pages_are_read_whole() {
+        let content = read_html_file("files/malformed.html").unwrap();
+        let sections = content.texts();
+
+        assert_eq!(
+            sections,
+            vec![
+                (Field::Title, "Café & Bar"),
+                (Field::Heading, "Opening hours"),
+                (
+                    Field::Body,
+                    "Open\u{a0}daily — from 9 to 5 Bold and italic words © 2024"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn attributes_are_fields_of_their_own() {
+        let content = read_html_file("files/attributes.html").unwrap();
+        let sections = content.texts();
+
+        assert_eq!(
+            sections,
+            vec![
+                (Field::Title, "Trail Guide"),
+                (Field::Description, "Hiking routes around the lake"),
+                (Field::Keywords, "hiking, trails, maps"),
+                (Field::Heading, "Routes"),
+                (Field::Body, "Start at the VC parking."),
+                (Field::Tooltip, "Visitor Center"),
+                (Field::Alt, "Map of the northern loop"),
+                (Field::Body, "Read the safety notes first."),
+            ]
+        );
+
+        // Link text stays in its paragraph, marked as a link
+        let link = |word: &str| {
+            let start = sections[7].1.find(word).unwrap();
+            content.mark_at(7, &(start..start + word.len()))
+        };
+        assert_eq!(link("safety"), Some(Field::Link));
+        assert_eq!(link("notes"), Some(Field::Link));
+        assert_eq!(link("first"), None);
+    }
+}
@@ -0,0 +1,114 @@
+use crate::file_types::{Content, Field};
+use pulldown_cmark::{Event, HeadingLevel, MetadataBlockKind, Options, Parser, Tag, TagEnd};
+use std::fs;
+use std::io::Result as IOResult;
+use std::path::Path;
+
+//  Possible Errors ->
+//      File Open: NotFound, Permission Denied
+//      Read: InvalidData(Non Utf8)
+
+// Text without the markup, the first top level heading (or a `title:` in the front matter)
+// is the title, other headings are their own field and code blocks are part of the body
+pub fn read_markdown_file<P: AsRef<Path>>(file_path: P) -> IOResult<Content> {
+    let source = fs::read_to_string(file_path)?;
+
+    let options = Options::ENABLE_TABLES
+        | Options::ENABLE_STRIKETHROUGH
+        | Options::ENABLE_TASKLISTS
+        | Options::ENABLE_FOOTNOTES
+        | Options::ENABLE_YAML_STYLE_METADATA_BLOCKS;
+    let parser = Parser::new_ext(&source, options);
+
+    let mut content = Content::new();
+    let mut has_title = false;
+    let mut in_metadata = false;
+
+    // Text of the block being read, inline markup does not split words
+    let mut block = String::new();
+    let mut field = Field::Body;
+
+    for event in parser {
+        match event {
+            Event::Start(Tag::Heading { level, .. }) => {
+                flush(&mut content, field, &mut block);
+                field = if level == HeadingLevel::H1 && !has_title {
+                    has_title = true;
+                    Field::Title
+                } else {
+                    Field::Heading
+                };
+            }
+            Event::Start(Tag::MetadataBlock(MetadataBlockKind::YamlStyle)) => in_metadata = true,
+            Event::End(TagEnd::MetadataBlock(_)) => in_metadata = false,
+            Event::Text(text) if in_metadata => {
+                let title = text
+                    .lines()
+                    .find_map(|line| line.strip_prefix("title:"))
+                    .map(|title| title.trim().trim_matches(['"', '\'']));
+
+                if let Some(title) = title.filter(|title| !title.is_empty()) {
+                    has_title = true;
+                    content.push(Field::Title, title);
+                }
+            }
+            // Link and image targets are left out, their text and alt text are kept
+            Event::Text(text) | Event::Code(text) => block.push_str(&text),
+            Event::SoftBreak | Event::HardBreak => block.push(' '),
+            Event::End(TagEnd::Heading(_)) => {
+                flush(&mut content, field, &mut block);
+                field = Field::Body;
+            }
+            // Blocks end a word, the code of a code block is kept as it is written
+            Event::End(
+                TagEnd::Paragraph
+                | TagEnd::CodeBlock
+                | TagEnd::Item
+                | TagEnd::TableCell
+                | TagEnd::BlockQuote(_)
+                | TagEnd::FootnoteDefinition,
+            )
+            | Event::Rule => flush(&mut content, field, &mut block),
+            // Raw HTML is markup as well
+            _ => {}
+        }
+    }
+
+    flush(&mut content, field, &mut block);
+    Ok(content)
+}
+
+fn flush(content: &mut Content, field: Field, block: &mut String) {
+    if !block.trim().is_empty() {
+        content.push(field, block.trim());
+    }
+    block.clear();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::read_markdown_file;
+    use crate::file_types::Field;
+
+    #[test]
+    fn markup_is_removed() {
+        let content = read_markdown_file("files/notes.md").unwrap();
+        let sections = content.texts();
+
+        assert_eq!(
+            sections,
+            vec![
+                (Field::Title, "Search Notes"),
+                (
+                    Field::Body,
+                    "Lsert indexes local files. See the guide for more."
+                ),
+                (Field::Heading, "Error handling"),
+                (
+                    Field::Body,
+                    "Errors are io::Result values. fn main() {\n    run().unwrap();\n} Read the logs"
+                ),
+            ]
+        );
+    }
+}
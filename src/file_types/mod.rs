@@ -1,13 +1,234 @@
+use serde::{Deserialize, Serialize};
 use std::fs;
 use std::io::Result as IOResult;
-use std::path::Path;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
 
+mod epub_file;
+mod html_file;
+mod markdown_file;
+mod office_file;
+mod pdf_file;
 mod xml_file;
 
 // Re-exports
+pub use epub_file::read_epub_file;
+pub use html_file::read_html_file;
+pub use markdown_file::read_markdown_file;
+pub use office_file::{read_docx_file, read_ods_file, read_odt_file, read_xlsx_file};
+pub use pdf_file::read_pdf_file;
 pub use xml_file::read_xml_file;
 
-pub fn read_plain_file<P: AsRef<Path>>(file_path: P) -> IOResult<String> {
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Field {
+    Title,
+    Heading,
+    Body,
+    Path,
+    // `<meta name="description">` and `<meta name="keywords">` of HTML pages
+    Description,
+    Keywords,
+    // `alt` text of images and `title` attributes of HTML elements
+    Alt,
+    Tooltip,
+    // Text of HTML links
+    Link,
+}
+
+impl FromStr for Field {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "title" => Ok(Field::Title),
+            "heading" => Ok(Field::Heading),
+            "body" => Ok(Field::Body),
+            "path" => Ok(Field::Path),
+            "description" => Ok(Field::Description),
+            "keywords" => Ok(Field::Keywords),
+            "alt" => Ok(Field::Alt),
+            "tooltip" => Ok(Field::Tooltip),
+            "link" => Ok(Field::Link),
+            _ => Err(format!("{s:?} is not a valid field")),
+        }
+    }
+}
+
+// Where a section is inside its file, reported with the snippets found in it
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Location {
+    Page(usize),
+    Sheet(String),
+}
+
+// Extracted text of a file, split into fields in document order
+#[derive(Default, Debug)]
+pub struct Content {
+    sections: Vec<(Field, String)>,
+    // Location of every section, for files split into pages or sheets
+    locations: Vec<Option<Location>>,
+    // Byte ranges inside a section with a field of their own, like the links of a paragraph
+    marks: Vec<(usize, Range<usize>, Field)>,
+    // Chapters of a book by name, each one is a document of its own, see `chapter_path`
+    chapters: Vec<(String, Content)>,
+}
+
+impl Content {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Text following a section of the same field is appended to it
+    pub fn push(&mut self, field: Field, text: &str) {
+        self.append(None, field, text);
+    }
+
+    // Text of a page or a sheet, never appended to a section of another one
+    pub fn push_at(&mut self, location: Location, field: Field, text: &str) {
+        self.append(Some(location), field, text);
+    }
+
+    // Text with runs of another field inside it, given as byte ranges of `text`.
+    // They weigh as their field but stay part of the text, so phrases still run across them
+    pub fn push_marked(&mut self, field: Field, text: &str, marks: &[(Range<usize>, Field)]) {
+        let start = self.append(None, field, text);
+        let section = self.sections.len() - 1;
+
+        for (range, field) in marks {
+            let range = start + range.start..start + range.end;
+            self.marks.push((section, range, *field));
+        }
+    }
+
+    // Byte offset the text starts at inside its section
+    fn append(&mut self, location: Option<Location>, field: Field, text: &str) -> usize {
+        match (self.sections.last_mut(), self.locations.last()) {
+            (Some((last, section)), Some(last_location))
+                if *last == field && *last_location == location =>
+            {
+                section.push(' ');
+                let start = section.len();
+                section.push_str(text);
+                start
+            }
+            _ => {
+                self.sections.push((field, text.to_string()));
+                self.locations.push(location);
+                0
+            }
+        }
+    }
+
+    // Field of the mark a byte range of a section starts in, if any.
+    // Marks are pushed in document order and never overlap, so the last one starting before it
+    // is the only one it can be in
+    pub fn mark_at(&self, section: usize, span: &Range<usize>) -> Option<Field> {
+        let after = self
+            .marks
+            .partition_point(|(marked, range, _)| (*marked, range.start) <= (section, span.start));
+        let (marked, range, field) = &self.marks[after.checked_sub(1)?];
+        (*marked == section && range.contains(&span.start)).then_some(*field)
+    }
+
+    pub fn location_of(&self, section: usize) -> Option<&Location> {
+        self.locations.get(section)?.as_ref()
+    }
+
+    pub fn sections(&self) -> &[(Field, String)] {
+        &self.sections
+    }
+
+    pub fn push_chapter(&mut self, name: &str, chapter: Content) {
+        self.chapters.push((name.to_string(), chapter));
+    }
+
+    pub fn chapters(&self) -> &[(String, Content)] {
+        &self.chapters
+    }
+
+    pub fn into_chapters(self) -> Vec<(String, Content)> {
+        self.chapters
+    }
+}
+
+#[cfg(test)]
+impl Content {
+    // Field and text of every section, the way reader tests compare them
+    pub fn texts(&self) -> Vec<(Field, &str)> {
+        self.sections
+            .iter()
+            .map(|(field, text)| (*field, text.as_str()))
+            .collect()
+    }
+
+    // Same with the page or sheet of every section
+    pub fn located_texts(&self) -> Vec<(Option<Location>, Field, &str)> {
+        self.texts()
+            .into_iter()
+            .zip(&self.locations)
+            .map(|((field, text), location)| (location.clone(), field, text))
+            .collect()
+    }
+}
+
+// Path of a chapter of a book, as in `book.epub#chapter3`
+pub fn chapter_path(path: &Path, chapter: &str) -> PathBuf {
+    let mut path = path.as_os_str().to_owned();
+    path.push("#");
+    path.push(chapter);
+    PathBuf::from(path)
+}
+
+// Book and chapter of the path of a chapter, None for the path of a file
+pub fn split_chapter(path: &Path) -> Option<(PathBuf, String)> {
+    if path.is_file() {
+        return None;
+    }
+
+    let (book, chapter) = path.to_str()?.rsplit_once('#')?;
+    Some((PathBuf::from(book), chapter.to_string()))
+}
+
+pub type Reader = fn(&Path) -> IOResult<Content>;
+
+// Source files with strings in double quotes, where 'c' is a character
+pub const C_LIKE_SOURCE: &[&str] = &[
+    "c", "cc", "cpp", "cs", "go", "h", "hpp", "java", "kt", "rs", "swift",
+];
+// Source files with strings in single, double or back quotes
+pub const SCRIPT_SOURCE: &[&str] = &["js", "jsx", "mjs", "php", "py", "rb", "sh", "ts", "tsx"];
+
+// Extractor for the extension of the file, None when it cannot be tokenized
+pub fn reader_for(path: &Path) -> Option<Reader> {
+    let extension = path.extension()?.to_str()?;
+
+    match extension {
+        "html" | "htm" => Some(|path| read_html_file(path)),
+        // Well-formed XML only
+        "xhtml" | "xml" => Some(|path| read_xml_file(path)),
+        "txt" => Some(|path| read_plain_file(path)),
+        "md" | "markdown" => Some(|path| read_markdown_file(path)),
+        "pdf" => Some(|path| read_pdf_file(path)),
+        "epub" => Some(|path| read_epub_file(path)),
+        "docx" => Some(|path| read_docx_file(path)),
+        "odt" => Some(|path| read_odt_file(path)),
+        "xlsx" => Some(|path| read_xlsx_file(path)),
+        "ods" => Some(|path| read_ods_file(path)),
+        // Split into identifiers by the analyzer registered for source code
+        extension if C_LIKE_SOURCE.contains(&extension) || SCRIPT_SOURCE.contains(&extension) => {
+            Some(|path| read_plain_file(path))
+        }
+        _ => None,
+    }
+}
+
+pub fn read_plain_file<P: AsRef<Path>>(file_path: P) -> IOResult<Content> {
     let source = fs::read_to_string(file_path)?;
-    Ok(source)
+
+    let mut content = Content::new();
+    content.push(Field::Body, &source);
+    Ok(content)
 }
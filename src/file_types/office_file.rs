@@ -0,0 +1,303 @@
+use crate::file_types::xml_file::{attribute, elements, read_xml, Schema, METADATA};
+use crate::file_types::{Content, Field, Location};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{Read, Result as IOResult, Seek};
+use std::path::Path;
+use xml::reader::{EventReader, XmlEvent};
+use zip::ZipArchive;
+
+//  Possible Errors ->
+//      File Open: NotFound, Permission Denied
+//      Unzip, missing document part: InvalidData
+
+// Paragraphs of a Word document, the runs of a paragraph are joined
+const WORD: Schema = Schema {
+    field_of: |_| None,
+    breaks: |element| matches!(element, "p" | "tab" | "br" | "cr" | "tc"),
+    trim_whitespace: false,
+};
+
+// Paragraphs and headings of an OpenDocument text
+const OPEN_DOCUMENT: Schema = Schema {
+    field_of: |element| (element == "h").then_some(Field::Heading),
+    breaks: |element| {
+        matches!(
+            element,
+            "p" | "h" | "s" | "tab" | "line-break" | "list-item" | "table-cell"
+        )
+    },
+    trim_whitespace: false,
+};
+
+pub fn read_docx_file<P: AsRef<Path>>(file_path: P) -> IOResult<Content> {
+    let mut archive = ZipArchive::new(File::open(file_path)?)?;
+
+    let mut content = Content::new();
+    push_title(&mut content, &mut archive, "docProps/core.xml");
+    append(
+        &mut content,
+        read_xml(archive.by_name("word/document.xml")?, &WORD),
+    );
+    for notes in ["word/footnotes.xml", "word/endnotes.xml"] {
+        if let Ok(part) = archive.by_name(notes) {
+            append(&mut content, read_xml(part, &WORD));
+        }
+    }
+
+    Ok(content)
+}
+
+pub fn read_odt_file<P: AsRef<Path>>(file_path: P) -> IOResult<Content> {
+    let mut archive = ZipArchive::new(File::open(file_path)?)?;
+
+    let mut content = Content::new();
+    push_title(&mut content, &mut archive, "meta.xml");
+    let document = read_xml(archive.by_name("content.xml")?, &OPEN_DOCUMENT);
+    append(&mut content, document);
+
+    Ok(content)
+}
+
+// Cell text of every sheet, located by the sheet name which is a heading of its own
+pub fn read_xlsx_file<P: AsRef<Path>>(file_path: P) -> IOResult<Content> {
+    let mut archive = ZipArchive::new(File::open(file_path)?)?;
+
+    let shared_strings = match archive.by_name("xl/sharedStrings.xml") {
+        Ok(part) => read_shared_strings(part),
+        Err(_) => Vec::new(),
+    };
+
+    // Sheets are named in the workbook and found through its relationships
+    let targets = elements(
+        archive.by_name("xl/_rels/workbook.xml.rels")?,
+        "Relationship",
+    )
+    .into_iter()
+    .filter_map(|attributes| {
+        let target = attribute(&attributes, "Target")?;
+        let target = match target.strip_prefix('/') {
+            Some(absolute) => absolute.to_string(),
+            None => format!("xl/{target}"),
+        };
+        Some((attribute(&attributes, "Id")?.to_string(), target))
+    })
+    .collect::<HashMap<_, _>>();
+    let sheets = elements(archive.by_name("xl/workbook.xml")?, "sheet")
+        .into_iter()
+        .filter_map(|attributes| {
+            let name = attribute(&attributes, "name")?.to_string();
+            let target = targets.get(attribute(&attributes, "id")?)?.clone();
+            Some((name, target))
+        })
+        .collect::<Vec<_>>();
+
+    let mut content = Content::new();
+    for (name, target) in sheets {
+        let Ok(part) = archive.by_name(&target) else {
+            continue;
+        };
+
+        let cells = read_cells(part, &shared_strings);
+        push_sheet(&mut content, name, &cells.join(" "));
+    }
+
+    Ok(content)
+}
+
+pub fn read_ods_file<P: AsRef<Path>>(file_path: P) -> IOResult<Content> {
+    let mut archive = ZipArchive::new(File::open(file_path)?)?;
+    let part = archive.by_name("content.xml")?;
+
+    let mut content = Content::new();
+    let mut sheet = None;
+    let mut cells = String::new();
+
+    for event in EventReader::new(part).into_iter().flatten() {
+        match event {
+            XmlEvent::StartElement {
+                name, attributes, ..
+            } if name.local_name == "table" => {
+                sheet = attribute(&attributes, "name").map(str::to_string);
+            }
+            XmlEvent::EndElement { name } if name.local_name == "table" => {
+                if let Some(name) = sheet.take() {
+                    push_sheet(&mut content, name, &cells);
+                }
+                cells.clear();
+            }
+            XmlEvent::EndElement { name } if matches!(name.local_name.as_str(), "p" | "s") => {
+                cells.push(' ');
+            }
+            XmlEvent::Characters(text) if sheet.is_some() => cells.push_str(&text),
+            _ => {}
+        }
+    }
+
+    Ok(content)
+}
+
+fn push_sheet(content: &mut Content, name: String, cells: &str) {
+    let location = Location::Sheet(name.clone());
+    content.push_at(location.clone(), Field::Heading, &name);
+    if !cells.trim().is_empty() {
+        content.push_at(location, Field::Body, cells.trim());
+    }
+}
+
+fn push_title<R: Read + Seek>(content: &mut Content, archive: &mut ZipArchive<R>, part: &str) {
+    let Ok(part) = archive.by_name(part) else {
+        return;
+    };
+
+    let metadata = read_xml(part, &METADATA);
+    for (field, text) in metadata.sections() {
+        if *field == Field::Title {
+            content.push(Field::Title, text);
+        }
+    }
+}
+
+fn append(content: &mut Content, part: Content) {
+    for (field, text) in part.sections() {
+        content.push(*field, text);
+    }
+}
+
+// Text of every string item, rich text runs are joined
+fn read_shared_strings<R: Read>(part: R) -> Vec<String> {
+    let mut strings = Vec::new();
+    let mut item = String::new();
+    let mut in_text = false;
+
+    for event in EventReader::new(part).into_iter().flatten() {
+        match event {
+            XmlEvent::StartElement { name, .. } if name.local_name == "t" => in_text = true,
+            XmlEvent::EndElement { name } if name.local_name == "t" => in_text = false,
+            XmlEvent::EndElement { name } if name.local_name == "si" => {
+                strings.push(std::mem::take(&mut item));
+            }
+            XmlEvent::Characters(text) | XmlEvent::Whitespace(text) if in_text => {
+                item.push_str(&text);
+            }
+            _ => {}
+        }
+    }
+
+    strings
+}
+
+// Values of the cells of a sheet, shared strings are looked up by their index
+fn read_cells<R: Read>(part: R, shared_strings: &[String]) -> Vec<String> {
+    let mut cells = Vec::new();
+    let mut shared = false;
+    let mut in_formula = false;
+    let mut value = String::new();
+
+    for event in EventReader::new(part).into_iter().flatten() {
+        match event {
+            XmlEvent::StartElement {
+                name, attributes, ..
+            } if name.local_name == "c" => {
+                shared = attribute(&attributes, "t") == Some("s");
+                value.clear();
+            }
+            XmlEvent::EndElement { name } if name.local_name == "c" => {
+                let text = if shared {
+                    value
+                        .trim()
+                        .parse::<usize>()
+                        .ok()
+                        .and_then(|i| shared_strings.get(i))
+                        .cloned()
+                } else {
+                    Some(value.trim().to_string())
+                };
+
+                cells.extend(text.filter(|text| !text.is_empty()));
+            }
+            // Formulas are not part of the value
+            XmlEvent::StartElement { name, .. } if name.local_name == "f" => in_formula = true,
+            XmlEvent::EndElement { name } if name.local_name == "f" => in_formula = false,
+            XmlEvent::Characters(text) if !in_formula => value.push_str(&text),
+            _ => {}
+        }
+    }
+
+    cells
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{read_docx_file, read_ods_file, read_odt_file, read_xlsx_file};
+    use crate::file_types::{Field, Location};
+
+    #[test]
+    fn runs_of_a_paragraph_are_joined() {
+        let content = read_docx_file("files/report.docx").unwrap();
+        let sections = content.texts();
+
+        assert_eq!(
+            sections,
+            vec![
+                (Field::Title, "Quarterly Report"),
+                (Field::Body, "Local searching works. Second paragraph"),
+            ]
+        );
+    }
+
+    #[test]
+    fn headings_and_paragraphs_of_an_open_document() {
+        let content = read_odt_file("files/minutes.odt").unwrap();
+        let sections = content.texts();
+
+        assert_eq!(
+            sections,
+            vec![
+                (Field::Title, "Meeting Minutes"),
+                (Field::Heading, "Team Meeting"),
+                (Field::Body, "Indexing is fast now. Next steps follow"),
+                (Field::Heading, "Actions"),
+                (Field::Body, "Ship the release"),
+            ]
+        );
+    }
+
+    #[test]
+    fn cells_are_located_by_sheet() {
+        let content = read_xlsx_file("files/budget.xlsx").unwrap();
+
+        let sheets = content.located_texts();
+
+        let costs = Some(Location::Sheet("Costs".to_string()));
+        let notes = Some(Location::Sheet("Notes".to_string()));
+        assert_eq!(
+            sheets,
+            vec![
+                (costs.clone(), Field::Heading, "Costs"),
+                (costs, Field::Body, "Hosting 120 Backups 240"),
+                (notes.clone(), Field::Heading, "Notes"),
+                (notes, Field::Body, "Renew in March"),
+            ]
+        );
+    }
+
+    #[test]
+    fn open_document_cells_are_located_by_sheet() {
+        let content = read_ods_file("files/inventory.ods").unwrap();
+
+        let sheets = content.located_texts();
+
+        let stock = Some(Location::Sheet("Stock".to_string()));
+        let orders = Some(Location::Sheet("Orders".to_string()));
+        assert_eq!(
+            sheets,
+            vec![
+                (stock.clone(), Field::Heading, "Stock"),
+                (stock, Field::Body, "Laptops 12 Monitors 30"),
+                (orders.clone(), Field::Heading, "Orders"),
+                (orders, Field::Body, "Reorder in May"),
+            ]
+        );
+    }
+}
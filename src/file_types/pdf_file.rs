@@ -0,0 +1,71 @@
+use crate::file_types::{Content, Field, Location};
+use lopdf::Document;
+use std::io::{Error, ErrorKind, Result as IOResult};
+use std::path::Path;
+
+//  Possible Errors ->
+//      File Open: NotFound, Permission Denied
+//      Parse: InvalidData
+//      Encrypted: PermissionDenied
+//      No text (scanned images): InvalidData
+
+// Text of every page, with its page number. Pages that cannot be decoded are left out
+pub fn read_pdf_file<P: AsRef<Path>>(file_path: P) -> IOResult<Content> {
+    let document = Document::load(file_path).map_err(|err| match err {
+        lopdf::Error::IO(err) => err,
+        err => Error::new(ErrorKind::InvalidData, err.to_string()),
+    })?;
+
+    // Documents with an empty user password are decrypted when they are loaded
+    if document.is_encrypted() {
+        return Err(Error::new(
+            ErrorKind::PermissionDenied,
+            "the PDF is encrypted",
+        ));
+    }
+
+    let mut content = Content::new();
+    for page in document.get_pages().into_keys() {
+        let Ok(text) = document.extract_text(&[page]) else {
+            continue;
+        };
+
+        if !text.trim().is_empty() {
+            content.push_at(Location::Page(page as usize), Field::Body, text.trim());
+        }
+    }
+
+    if content.sections().is_empty() {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            "the PDF has no text, its pages may be scanned images",
+        ));
+    }
+
+    Ok(content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::read_pdf_file;
+    use crate::file_types::Location;
+
+    #[test]
+    fn text_of_every_page() {
+        let content = read_pdf_file("files/pages.pdf").unwrap();
+
+        let pages = content
+            .located_texts()
+            .into_iter()
+            .map(|(location, _, text)| (location, text.trim()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            pages,
+            vec![
+                (Some(Location::Page(1)), "Searching local files"),
+                (Some(Location::Page(2)), "Ranking with BM25"),
+            ]
+        );
+    }
+}
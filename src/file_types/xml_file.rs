@@ -1,17 +1,46 @@
+use crate::file_types::{html_file, Content, Field};
 use std::fs::File;
-use std::io::Result as IOResult;
+use std::io::{Read, Result as IOResult};
 use std::path::Path;
+use xml::attribute::OwnedAttribute;
 use xml::reader::{EventReader, ParserConfig, XmlEvent};
 
+// How the elements of an XML vocabulary map to the content
+pub(super) struct Schema {
+    // Field of the text inside an element, by its local name
+    pub field_of: fn(&str) -> Option<Field>,
+    // Elements that end a run of text, the text between them is joined as it is
+    pub breaks: fn(&str) -> bool,
+    // Whitespace around text is markup, not part of it
+    pub trim_whitespace: bool,
+}
+
+pub(super) const XHTML: Schema = Schema {
+    field_of: html_file::field_of,
+    breaks: |_| true,
+    trim_whitespace: true,
+};
+
+// `title` of the Dublin Core metadata of Office documents and e-books
+pub(super) const METADATA: Schema = Schema {
+    field_of: |element| (element == "title").then_some(Field::Title),
+    breaks: |_| true,
+    trim_whitespace: true,
+};
+
 //  Possible Errors ->
 //      File Opening: NotFound, Permission Denied, InvalidInput
 
 // Handling Every Error When Deserializing a XML File
-pub fn read_xml_file<P: AsRef<Path>>(file_path: P) -> IOResult<String> {
+pub fn read_xml_file<P: AsRef<Path>>(file_path: P) -> IOResult<Content> {
     let source = File::open(file_path)?;
+    Ok(read_xml(source, &XHTML))
+}
 
+// Text of any XML source, like the parts of an Office document
+pub(super) fn read_xml<R: Read>(source: R, schema: &Schema) -> Content {
     let parser_config = ParserConfig {
-        trim_whitespace: true,
+        trim_whitespace: schema.trim_whitespace,
         whitespace_to_characters: false,
         cdata_to_characters: true,
         ignore_comments: false,
@@ -24,17 +53,82 @@ pub fn read_xml_file<P: AsRef<Path>>(file_path: P) -> IOResult<String> {
 
     let chars_content = EventReader::new_with_config(source, parser_config);
 
-    let mut content = String::new();
+    let mut content = Content::new();
+
+    // Depth of the elements of the title and heading fields
+    let mut title_depth = 0;
+    let mut heading_depth = 0;
+    // Text of the current run
+    let mut run = String::new();
 
     // TODO: Handle Err Variant, Maybe???
-    for data in chars_content {
-        if let Ok(character_string_result) = data {
-            if let XmlEvent::Characters(character_string) = character_string_result {
-                content.push_str(&character_string);
-                content.push_str(" ");
+    for data in chars_content.into_iter().flatten() {
+        let field = if title_depth > 0 {
+            Field::Title
+        } else if heading_depth > 0 {
+            Field::Heading
+        } else {
+            Field::Body
+        };
+
+        match data {
+            XmlEvent::StartElement { name, .. } => {
+                let field_of = (schema.field_of)(&name.local_name);
+                if field_of.is_some() || (schema.breaks)(&name.local_name) {
+                    push_run(&mut content, field, &mut run);
+                }
+
+                match field_of {
+                    Some(Field::Title) => title_depth += 1,
+                    Some(Field::Heading) => heading_depth += 1,
+                    _ => {}
+                }
             }
+            XmlEvent::EndElement { name } => {
+                let field_of = (schema.field_of)(&name.local_name);
+                if field_of.is_some() || (schema.breaks)(&name.local_name) {
+                    push_run(&mut content, field, &mut run);
+                }
+
+                match field_of {
+                    Some(Field::Title) => title_depth -= 1,
+                    Some(Field::Heading) => heading_depth -= 1,
+                    _ => {}
+                }
+            }
+            XmlEvent::Characters(text) | XmlEvent::Whitespace(text) => run.push_str(&text),
+            _ => {}
         }
     }
 
-    Ok(content)
+    push_run(&mut content, Field::Body, &mut run);
+    content
+}
+
+fn push_run(content: &mut Content, field: Field, run: &mut String) {
+    if !run.trim().is_empty() {
+        content.push(field, run.trim());
+    }
+    run.clear();
+}
+
+// Attributes of every element with the local name
+pub(super) fn elements<R: Read>(part: R, element: &str) -> Vec<Vec<OwnedAttribute>> {
+    EventReader::new(part)
+        .into_iter()
+        .flatten()
+        .filter_map(|event| match event {
+            XmlEvent::StartElement {
+                name, attributes, ..
+            } if name.local_name == element => Some(attributes),
+            _ => None,
+        })
+        .collect()
+}
+
+pub(super) fn attribute<'a>(attributes: &'a [OwnedAttribute], name: &str) -> Option<&'a str> {
+    attributes
+        .iter()
+        .find(|attribute| attribute.name.local_name == name)
+        .map(|attribute| attribute.value.as_str())
 }
@@ -0,0 +1,250 @@
+use crate::query::{Clause, Query};
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::ops::Range;
+
+// Vocabulary terms a query term is expanded to, at most
+const MAX_EXPANSIONS: usize = 3;
+
+pub struct Correction {
+    pub term: String,
+    // Closest vocabulary terms first
+    pub candidates: Vec<String>,
+}
+
+// Short words get fewer typos, so "cat" does not turn into every three letter word
+fn max_distance(term: &str) -> usize {
+    match term.chars().count() {
+        0..=2 => 0,
+        3..=5 => 1,
+        _ => 2,
+    }
+}
+
+// Optimal string alignment distance, Levenshtein with swapped neighbours as a single edit,
+// so "rsut" is one typo away from "rust". Given up on as soon as it is sure to go over `max`
+pub fn edit_distance(a: &str, b: &str, max: usize) -> Option<usize> {
+    let a = a.chars().collect::<Vec<_>>();
+    let b = b.chars().collect::<Vec<_>>();
+
+    if a.len().abs_diff(b.len()) > max {
+        return None;
+    }
+
+    let mut before_previous = vec![0; b.len() + 1];
+    let mut previous = (0..=b.len()).collect::<Vec<_>>();
+    let mut current = vec![0; b.len() + 1];
+
+    for i in 1..=a.len() {
+        current[0] = i;
+        for j in 1..=b.len() {
+            let substitution = previous[j - 1] + usize::from(a[i - 1] != b[j - 1]);
+            current[j] = substitution.min(previous[j] + 1).min(current[j - 1] + 1);
+
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                current[j] = current[j].min(before_previous[j - 2] + 1);
+            }
+        }
+
+        if current.iter().min().is_some_and(|&min| min > max) {
+            return None;
+        }
+        std::mem::swap(&mut before_previous, &mut previous);
+        std::mem::swap(&mut previous, &mut current);
+    }
+
+    Some(previous[b.len()]).filter(|&distance| distance <= max)
+}
+
+// Query terms that are not in the vocabulary, with the vocabulary terms close to them.
+// A term only found in phrases has a single candidate, the one `expand` searches for
+pub fn corrections(query: &Query, vocabulary: &HashMap<String, usize>) -> Vec<Correction> {
+    let mut terms = Vec::new();
+    // Terms searched for on their own, and not only inside a phrase
+    let mut alone = Vec::new();
+    for clause in query.positive_clauses() {
+        match clause {
+            Clause::Term(term) => {
+                terms.push(term);
+                alone.push(term);
+            }
+            Clause::Phrase(phrase) => terms.extend(phrase),
+        }
+    }
+
+    let mut corrections: Vec<Correction> = Vec::new();
+    for term in terms {
+        let max = max_distance(term);
+        if max == 0
+            || !term.chars().any(char::is_alphabetic)
+            || vocabulary.contains_key(term.as_str())
+            || corrections.iter().any(|correction| &correction.term == term)
+        {
+            continue;
+        }
+
+        let mut candidates = vocabulary
+            .iter()
+            .filter_map(|(word, frequency)| {
+                edit_distance(term, word, max).map(|distance| (distance, *frequency, word))
+            })
+            .collect::<Vec<_>>();
+
+        // Closer first, then the more frequent one
+        candidates.sort_by(|a, b| a.0.cmp(&b.0).then(b.1.cmp(&a.1)).then(a.2.cmp(b.2)));
+
+        if !candidates.is_empty() {
+            corrections.push(Correction {
+                term: term.clone(),
+                candidates: candidates
+                    .into_iter()
+                    .take(if alone.contains(&term) {
+                        MAX_EXPANSIONS
+                    } else {
+                        1
+                    })
+                    .map(|(_, _, word)| word.to_string())
+                    .collect(),
+            });
+        }
+    }
+
+    corrections
+}
+
+fn find<'c>(corrections: &'c [Correction], term: &str) -> Option<&'c Correction> {
+    corrections.iter().find(|correction| correction.term == term)
+}
+
+fn best(corrections: &[Correction], term: String) -> String {
+    match find(corrections, &term) {
+        Some(correction) => correction.candidates[0].clone(),
+        None => term,
+    }
+}
+
+// Misspelled terms match any of their candidates, inside a phrase only the closest one is used
+pub fn expand(query: Query, corrections: &[Correction]) -> Query {
+    query.map_clauses(&mut |clause| match clause {
+        Clause::Term(term) => match find(corrections, &term) {
+            Some(correction) => Query::Or(
+                correction
+                    .candidates
+                    .iter()
+                    .map(|candidate| Query::Clause(Clause::Term(candidate.clone())))
+                    .collect(),
+            ),
+            None => Query::Clause(Clause::Term(term)),
+        },
+        Clause::Phrase(phrase) => Query::Clause(Clause::Phrase(
+            phrase.into_iter().map(|term| best(corrections, term)).collect(),
+        )),
+    })
+}
+
+// The input with every misspelled term replaced by its closest candidate, the other words
+// are left as they were written. `spans` are the terms of the input, see `term_spans`
+pub fn did_you_mean(
+    input: &str,
+    spans: &[(String, Range<usize>)],
+    corrections: &[Correction],
+) -> String {
+    let mut replacements = spans
+        .iter()
+        .filter_map(|(term, span)| Some((span.clone(), &find(corrections, term)?.candidates[0])))
+        .collect::<Vec<_>>();
+    // The longest form of a word goes first, as "parse_qery" over "qery"
+    replacements.sort_by_key(|(span, _)| (span.start, Reverse(span.end)));
+
+    let mut suggestion = String::new();
+    let mut last = 0;
+    for (span, candidate) in replacements {
+        if span.start < last {
+            continue;
+        }
+
+        suggestion.push_str(&input[last..span.start]);
+        suggestion.push_str(candidate);
+        last = span.end;
+    }
+
+    suggestion.push_str(&input[last..]);
+    suggestion
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{corrections, did_you_mean, edit_distance};
+    use crate::lexical_analysis::AnalysisSettings;
+    use crate::query::{term_spans, Query};
+    use std::collections::HashMap;
+
+    #[test]
+    fn bounded_edit_distance() {
+        assert_eq!(edit_distance("handling", "handlign", 2), Some(1));
+        assert_eq!(edit_distance("rsut", "rust", 1), Some(1));
+        assert_eq!(edit_distance("ca", "abc", 2), None);
+        assert_eq!(edit_distance("rust", "rest", 1), Some(1));
+        assert_eq!(edit_distance("rust", "trust", 1), Some(1));
+        assert_eq!(edit_distance("rust", "crate", 2), None);
+    }
+
+    #[test]
+    fn misspelled_terms_are_suggested() {
+        let vocabulary = [("error", 10), ("handling", 4), ("rust", 7), ("rest", 2)]
+            .into_iter()
+            .map(|(term, frequency)| (term.to_string(), frequency))
+            .collect::<HashMap<_, _>>();
+
+        let analyzer = AnalysisSettings::default().analyzer();
+        let input = "\"eror handling\" rsut";
+        let query = Query::parse(input, &[&analyzer]).unwrap();
+        let corrections = corrections(&query, &vocabulary);
+
+        assert_eq!(corrections.len(), 2);
+        assert_eq!(corrections[0].term, "eror");
+        assert_eq!(
+            did_you_mean(input, &term_spans(input, &[&analyzer]), &corrections),
+            "\"error handling\" rust"
+        );
+    }
+
+    #[test]
+    fn phrases_search_the_closest_candidate_only() {
+        let vocabulary = [("rust", 7), ("rest", 2), ("guide", 3)]
+            .into_iter()
+            .map(|(term, frequency)| (term.to_string(), frequency))
+            .collect::<HashMap<_, _>>();
+        let analyzer = AnalysisSettings::default().analyzer();
+        let candidates = |input: &str| {
+            let query = Query::parse(input, &[&analyzer]).unwrap();
+            corrections(&query, &vocabulary).remove(0).candidates
+        };
+
+        assert_eq!(candidates("\"rast guide\""), vec!["rust"]);
+        assert_eq!(candidates("rast"), vec!["rust", "rest"]);
+        assert_eq!(candidates("\"rast guide\" rast"), vec!["rust", "rest"]);
+    }
+
+    #[test]
+    fn suggestions_keep_the_words_as_written() {
+        let vocabulary = [("error", 10), ("handl", 4)]
+            .into_iter()
+            .map(|(term, frequency)| (term.to_string(), frequency))
+            .collect::<HashMap<_, _>>();
+
+        let settings = AnalysisSettings {
+            stemming: true,
+            ..AnalysisSettings::default()
+        };
+        let analyzer = settings.analyzer();
+        let input = "Eror Handling";
+        let query = Query::parse(input, &[&analyzer]).unwrap();
+        let corrections = corrections(&query, &vocabulary);
+
+        assert_eq!(
+            did_you_mean(input, &term_spans(input, &[&analyzer]), &corrections),
+            "error Handling"
+        );
+    }
+}
@@ -0,0 +1,238 @@
+use crate::file_types::Field;
+use crate::lexical_analysis::AnalysisSettings;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Result as IOResult;
+use std::path::{Path, PathBuf};
+
+// Token positions of every term, in ascending order
+pub type TermPositions = HashMap<String, Vec<usize>>;
+// Times every term was written in each form it was made from, like "handling" for "handl"
+pub type Surfaces = HashMap<String, HashMap<String, usize>>;
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct Document {
+    // Number of tokens the document was split into
+    pub length: usize,
+    pub positions: TermPositions,
+    // Field of every section and the position it starts at, a section runs until the next one
+    pub fields: Vec<(Field, usize)>,
+    // Positions with a field other than the one of their section, like the words of a link
+    #[serde(default)]
+    pub marks: Vec<(Field, usize)>,
+    // Words as they were written, for the terms the analyzer changed, see `Index::surfaces`
+    #[serde(skip)]
+    pub surfaces: Surfaces,
+}
+
+impl Document {
+    pub fn start_field(&mut self, field: Field) {
+        // A section without any token is replaced
+        if let Some((_, start)) = self.fields.last() {
+            if *start == self.length {
+                self.fields.pop();
+            }
+        }
+
+        self.fields.push((field, self.length));
+    }
+
+    pub fn push(&mut self, token: String) {
+        self.positions.entry(token).or_default().push(self.length);
+        self.length += 1;
+    }
+
+    // A term at the same position as the last one, like "2" inside "1.2.3"
+    pub fn push_part(&mut self, token: String) {
+        let Some(position) = self.length.checked_sub(1) else {
+            return;
+        };
+
+        let positions = self.positions.entry(token).or_default();
+        if positions.last() != Some(&position) {
+            positions.push(position);
+        }
+    }
+
+    // `surface` is the normalized text a term was made from, kept when the analyzer changed it
+    pub fn record_surface(&mut self, term: &str, surface: String) {
+        if surface != term {
+            let forms = self.surfaces.entry(term.to_string()).or_default();
+            *forms.entry(surface).or_insert(0) += 1;
+        }
+    }
+
+    // The last token weighs as the field, without starting a section
+    pub fn mark(&mut self, field: Field) {
+        if let Some(position) = self.length.checked_sub(1) {
+            self.marks.push((field, position));
+        }
+    }
+
+    fn section_at(&self, position: usize) -> Option<usize> {
+        self.fields
+            .partition_point(|(_, start)| *start <= position)
+            .checked_sub(1)
+    }
+
+    // Documents indexed without sections are all body
+    pub fn field_at(&self, position: usize) -> Field {
+        if let Ok(mark) = self.marks.binary_search_by_key(&position, |(_, p)| *p) {
+            return self.marks[mark].0;
+        }
+
+        self.section_at(position)
+            .map_or(Field::Body, |section| self.fields[section].0)
+    }
+
+    pub fn term_occurrences(&self, term: &str) -> &[usize] {
+        self.positions.get(term).map_or(&[], Vec::as_slice)
+    }
+
+    // Start positions of the terms right next to each other, in the given order and in the same section
+    pub fn phrase_occurrences(&self, phrase: &[String]) -> Vec<usize> {
+        let Some((first, rest)) = phrase.split_first() else {
+            return Vec::new();
+        };
+
+        let mut followers = Vec::with_capacity(rest.len());
+        for term in rest {
+            match self.positions.get(term) {
+                Some(positions) => followers.push(positions),
+                None => return Vec::new(),
+            }
+        }
+
+        let end = rest.len();
+        self.term_occurrences(first)
+            .iter()
+            .copied()
+            .filter(|&start| {
+                followers
+                    .iter()
+                    .enumerate()
+                    .all(|(i, positions)| positions.binary_search(&(start + i + 1)).is_ok())
+                    && self.section_at(start) == self.section_at(start + end)
+            })
+            .collect()
+    }
+}
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct Index {
+    // Indexes written before the settings existed were built with the defaults
+    #[serde(default)]
+    pub settings: AnalysisSettings,
+    pub documents: HashMap<PathBuf, Document>,
+    // Written forms of the terms of every document, so stems are never shown to the user
+    #[serde(default)]
+    pub surfaces: Surfaces,
+}
+
+impl Index {
+    pub fn new(settings: AnalysisSettings) -> Self {
+        Self {
+            settings,
+            documents: HashMap::new(),
+            surfaces: Surfaces::new(),
+        }
+    }
+
+    //  Possible Errors ->
+    //      File Open: NotFound, Permission Denied
+    //      Deserialize: InvalidData
+    pub fn load(json_path: impl AsRef<Path>) -> IOResult<Self> {
+        let file = File::open(json_path)?;
+        let index = serde_json::from_reader(file)?;
+        Ok(index)
+    }
+
+    pub fn save(&self, json_path: impl AsRef<Path>) -> IOResult<()> {
+        let file = File::create(json_path)?;
+        serde_json::to_writer(file, self)?;
+        Ok(())
+    }
+
+    pub fn insert(&mut self, path: PathBuf, mut document: Document) {
+        for (term, forms) in document.surfaces.drain() {
+            let counts = self.surfaces.entry(term).or_default();
+            for (form, count) in forms {
+                *counts.entry(form).or_insert(0) += count;
+            }
+        }
+        self.documents.insert(path, document);
+    }
+
+    // The form every changed term was written in most often, ties go to the first alphabetically
+    pub fn surfaces(&self) -> HashMap<String, String> {
+        self.surfaces
+            .iter()
+            .filter_map(|(term, forms)| {
+                let (form, _) = forms
+                    .iter()
+                    .max_by(|a, b| a.1.cmp(b.1).then(b.0.cmp(a.0)))?;
+                Some((term.clone(), form.clone()))
+            })
+            .collect()
+    }
+
+    // Every indexed term with the number of times it occurs in the whole corpus
+    pub fn vocabulary(&self) -> HashMap<String, usize> {
+        let mut vocabulary = HashMap::new();
+
+        for document in self.documents.values() {
+            for (term, positions) in &document.positions {
+                *vocabulary.entry(term.clone()).or_insert(0) += positions.len();
+            }
+        }
+
+        vocabulary
+    }
+
+    pub fn average_length(&self) -> f64 {
+        if self.documents.is_empty() {
+            return 0.0;
+        }
+
+        let total = self.documents.values().map(|d| d.length).sum::<usize>();
+        total as f64 / self.documents.len() as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Document, Index};
+    use crate::analyzer::Analyzer;
+    use crate::lexical_analysis::{normalize, AnalysisSettings};
+    use std::path::PathBuf;
+
+    #[test]
+    fn stemming_is_saved_with_the_written_words() {
+        let settings = AnalysisSettings {
+            stemming: true,
+            ..AnalysisSettings::default()
+        };
+        let text = "Handling errors, handled errors and handling";
+
+        let mut document = Document::default();
+        for term in settings.analyzer().analyze(text) {
+            document.record_surface(&term.text, normalize(&text[term.span]));
+            document.push(term.text);
+        }
+        assert_eq!(document.term_occurrences("handl"), &[0, 3, 6]);
+
+        let mut index = Index::new(settings.clone());
+        index.insert(PathBuf::from("notes.txt"), document);
+
+        let json_path = std::env::temp_dir().join("lsert-stemming-index.json");
+        index.save(&json_path).unwrap();
+        let index = Index::load(&json_path).unwrap();
+        std::fs::remove_file(&json_path).unwrap();
+
+        assert_eq!(index.settings, settings);
+        let surfaces = index.surfaces();
+        assert_eq!(surfaces["handl"], "handling");
+        assert_eq!(surfaces["error"], "errors");
+    }
+}
@@ -1,43 +1,49 @@
-use crate::file_types::{
-    read_xml_file,
-    read_plain_file
-};
-use crate::lexical_analysis::Lexer;
-use std::collections::HashMap;
+use crate::analyzer::Analyzers;
+use crate::file_types::{chapter_path, reader_for, Content, Field};
+use crate::index::{Document, Index};
+use crate::lexical_analysis::{normalize, tokenize_content, AnalysisSettings};
 use std::fmt::{Display, Error as LogError, Formatter};
-use std::fs::{self, File};
+use std::fs;
 use std::io::{Error, ErrorKind, Result as IOResult};
-use std::path::PathBuf;
-
-type TermFreq = HashMap<String, usize>;
-type TermFreqIndex = HashMap<PathBuf, TermFreq>;
+use std::path::{Path, PathBuf};
 
 pub struct IOControl {
     path: PathBuf,
     json_path: String,
     deep: bool,
     progress: bool,
+    settings: AnalysisSettings,
+    analyzers: Analyzers,
 }
 
 impl IOControl {
-    pub fn new(path: PathBuf, json_path: &str, deep: bool, progress: bool) -> Self {
+    pub fn new(
+        path: PathBuf,
+        json_path: &str,
+        deep: bool,
+        progress: bool,
+        settings: AnalysisSettings,
+    ) -> Self {
         Self {
             path,
             json_path: json_path.to_string(),
             deep,
             progress,
+            analyzers: Analyzers::new(&settings),
+            settings,
         }
     }
 
     pub fn check_file_type(&self) -> IOResult<()> {
-        let mut tfi = TermFreqIndex::new();
+        let mut index = Index::new(self.settings.clone());
         let path = &self.path;
 
         if path.is_file() {
-            let content = self.read_file(path)?;
-            tfi.insert(path.clone(), content);
+            for (path, document) in self.read_file(path)? {
+                index.insert(path, document);
+            }
         } else if path.is_dir() {
-            self.read_dir(path, &mut tfi)?;
+            self.read_dir(path, &mut index)?;
         } else {
             return Err(Error::new(
                 ErrorKind::NotFound,
@@ -45,8 +51,7 @@ impl IOControl {
             ));
         }
 
-        let file = File::create(&self.json_path)?;
-        serde_json::to_writer(file, &tfi)?;
+        index.save(&self.json_path)?;
         Ok(())
     }
 
@@ -54,24 +59,24 @@ impl IOControl {
     //      path doesn't exist - NotFound
     //      lacks permission to view content - PermissionDenied
     //      points at a non-directory file - NotADirectory
-    fn read_dir(&self, path: &PathBuf, tfi: &mut TermFreqIndex) -> IOResult<()> {
-        let dir = fs::read_dir(&path)?;
+    fn read_dir(&self, path: &PathBuf, index: &mut Index) -> IOResult<()> {
+        let dir = fs::read_dir(path)?;
 
         for dir_entry in dir {
             let dir_path = dir_entry?.path();
 
-            let tf_option = if dir_path.is_file() {
-                Some(self.read_file(&dir_path)?)
+            let documents = if dir_path.is_file() {
+                self.read_file(&dir_path)?
             } else {
-                None
+                Vec::new()
             };
 
             if dir_path.is_dir() && self.deep {
-                self.read_dir(&dir_path, tfi)?;
+                self.read_dir(&dir_path, index)?;
             }
 
-            if let Some(tf) = tf_option {
-                tfi.insert(dir_path, tf);
+            for (path, document) in documents {
+                index.insert(path, document);
             }
         }
 
@@ -80,51 +85,74 @@ impl IOControl {
 
     //  Possible Errors:
     //      Not Found (Cannot Tokenize)
-    fn read_file(&self, path: &PathBuf) -> std::io::Result<TermFreq> {
+    //  Files of other types, or that cannot be extracted like encrypted PDFs, are warned about
+    //  and left out, so they do not count towards the document count and average length.
+    //  Every chapter of a book is a document of its own, at the path of its chapter
+    fn read_file(&self, path: &PathBuf) -> std::io::Result<Vec<(PathBuf, Document)>> {
         // TODO: Handle Errors
-        let path_extension = path.extension();
-        let mut tf = TermFreq::new();
-
-        if let Some(extension_osstr) = path_extension {
-            if let Some(extension) = extension_osstr.to_str() {
-                let content_option = match extension {
-                    "xhtml" | "html" | "xml" => {
-                        if self.progress {
-                            println!("Indexing {:?}", path);
-                        }
-                        Some(read_xml_file(path)?)
-                    }
-                    "txt" => {
-                        if self.progress {
-                            println!("Indexing {:?}", path);
-                        }
-                        Some(read_plain_file(path)?)
-                    }
-                    _ => {
-                        LogLevel::WARN(format!("Cannot Tokenize {}", path.display())).show();
-                        None
-                    }
-                };
-
-                if let Some(content) = content_option {
-                    let char_slice = content.chars().collect::<Vec<_>>();
-                    let lexer = Lexer::new(&char_slice);
-
-                    for token in lexer {
-                        if let Some(tok) = tf.get_mut(&token) {
-                            *tok += 1;
-                        } else {
-                            tf.insert(token, 1);
-                        }
-                    }
+        let Some(reader) = reader_for(path) else {
+            LogLevel::WARN(format!("Cannot Tokenize {}", path.display())).show();
+            return Ok(Vec::new());
+        };
+
+        if self.progress {
+            println!("Indexing {:?}", path);
+        }
+
+        let content = match reader(path) {
+            Ok(content) => content,
+            Err(err) => {
+                LogLevel::WARN(format!("Cannot Read {}: {err}", path.display())).show();
+                return Ok(Vec::new());
+            }
+        };
+
+        if content.chapters().is_empty() {
+            return Ok(vec![(path.clone(), self.document(path, content))]);
+        }
+
+        let documents = content
+            .into_chapters()
+            .into_iter()
+            .map(|(name, chapter)| (chapter_path(path, &name), self.document(path, chapter)))
+            .collect();
+        Ok(documents)
+    }
+
+    fn document(&self, path: &Path, mut content: Content) -> Document {
+        let mut document = Document::default();
+
+        if let Some(file_stem) = path.file_stem() {
+            content.push(Field::Path, &file_stem.to_string_lossy());
+        }
+
+        let mut section = None;
+        for token in tokenize_content(&content, self.analyzers.for_path(path)) {
+            if section != Some(token.section) {
+                section = Some(token.section);
+                document.start_field(content.sections()[token.section].0);
+            }
+
+            let written = &content.sections()[token.section].1[token.span.clone()];
+            if written != token.text {
+                document.record_surface(&token.text, normalize(written));
+            }
+
+            if token.part {
+                document.push_part(token.text);
+            } else {
+                document.push(token.text);
+                if let Some(field) = content.mark_at(token.section, &token.span) {
+                    document.mark(field);
                 }
             }
         }
 
-        Ok(tf)
+        document
     }
 }
 
+#[allow(clippy::upper_case_acronyms)]
 pub enum LogLevel {
     ERROR(String),
     WARN(String),
@@ -132,19 +160,21 @@ pub enum LogLevel {
 }
 
 impl Display for LogLevel {
-    fn fmt(&self, _f: &mut Formatter<'_>) -> Result<(), LogError> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), LogError> {
         match self {
-            LogLevel::ERROR(err) => eprint!("\x1B[41m\x1B[1mERROR:\x1B[0m {err}"),
-            LogLevel::WARN(warn) => eprint!("\x1B[44m\x1B[1mWARN:\x1B[0m {warn} "),
-            LogLevel::SIGNAL(signal) => print!("\n\x1B[1m{signal}\x1B[0m\n"),
-        };
-
-        Ok(())
+            LogLevel::ERROR(err) => write!(f, "\x1B[41m\x1B[1mERROR:\x1B[0m {err}"),
+            LogLevel::WARN(warn) => write!(f, "\x1B[44m\x1B[1mWARN:\x1B[0m {warn} "),
+            LogLevel::SIGNAL(signal) => write!(f, "\n\x1B[1m{signal}\x1B[0m\n"),
+        }
     }
 }
 
 impl LogLevel {
     pub fn show(&self) {
-        println!("{}", self);
+        match self {
+            LogLevel::ERROR(_) | LogLevel::WARN(_) => eprint!("{}", self),
+            LogLevel::SIGNAL(_) => print!("{}", self),
+        }
+        println!();
     }
 }
@@ -1,56 +1,229 @@
+use crate::analyzer::Analyzer;
+use crate::file_types::Content;
+use crate::stop_words::StopWords;
+use caseless::default_case_fold_str;
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+
 pub struct Lexer<'s> {
-    character_stream: &'s [char],
+    source: &'s str,
+    // Bytes consumed so far
+    offset: usize,
+    // Versions, decimals, IPs, URLs, emails and `_` identifiers kept as one token
+    special_tokens: bool,
 }
 
 impl<'s> Lexer<'s> {
-    pub fn new(character_stream: &'s [char]) -> Self {
-        Self { character_stream }
+    pub fn new(source: &'s str) -> Self {
+        Self {
+            source,
+            offset: 0,
+            special_tokens: false,
+        }
+    }
+
+    pub fn with_special_tokens(mut self, special_tokens: bool) -> Self {
+        self.special_tokens = special_tokens;
+        self
+    }
+
+    fn rest(&self) -> &'s str {
+        &self.source[self.offset..]
     }
 
     fn trim_whitespace(&mut self) {
-        while self.character_stream.len() > 0 && self.character_stream[0].is_whitespace() {
-            self.character_stream = &self.character_stream[1..];
-        }
+        let rest = self.rest();
+        self.offset += rest.len() - rest.trim_start().len();
     }
 
-    fn truncate(&mut self, n: usize) -> &'s [char] {
-        let token = &self.character_stream[0..n];
-        self.character_stream = &self.character_stream[n..];
+    fn truncate(&mut self, n: usize) -> &'s str {
+        let token = &self.rest()[..n];
+        self.offset += n;
         token
     }
 
-    fn truncate_while<F: FnMut(&char) -> bool>(&mut self, mut predicate: F) -> &'s [char] {
-        let mut n = 0;
-        while self.character_stream.len() > n && predicate(&self.character_stream[n]) {
-            n += 1;
-        }
+    fn truncate_while<F: FnMut(char) -> bool>(&mut self, mut predicate: F) -> &'s str {
+        let rest = self.rest();
+        let n = rest.find(|c| !predicate(c)).unwrap_or(rest.len());
         self.truncate(n)
     }
 
-    pub fn next_token(&mut self) -> Option<String> {
+    pub fn next_token(&mut self) -> Option<&'s str> {
         self.trim_whitespace();
-        if self.character_stream.len() == 0 {
-            return None;
+        let first = self.rest().chars().next()?;
+
+        if self.special_tokens {
+            if let Some(n) = special_token_length(self.rest()) {
+                return Some(self.truncate(n));
+            }
         }
 
-        if self.character_stream[0].is_alphabetic() {
-            return Some(
-                self.truncate_while(|x| x.is_alphanumeric())
-                    .iter()
-                    .collect(),
-            );
+        // Combining marks stay with their letter, so decomposed accents do not split a word
+        if first.is_alphabetic() {
+            return Some(self.truncate_while(|x| x.is_alphanumeric() || is_combining_mark(x)));
         }
 
-        if self.character_stream[0].is_numeric() {
-            return Some(self.truncate_while(|x| x.is_numeric()).iter().collect());
+        if first.is_numeric() {
+            return Some(self.truncate_while(|x| x.is_numeric()));
         }
 
-        return Some(self.truncate(1).iter().collect());
+        Some(self.truncate(first.len_utf8()))
+    }
+
+    // Token with its byte range in the source
+    pub fn next_token_span(&mut self) -> Option<(&'s str, Range<usize>)> {
+        self.trim_whitespace();
+        let start = self.offset;
+        let token = self.next_token()?;
+        Some((token, start..self.offset))
+    }
+}
+
+// Length of the special token `text` starts with, if any
+fn special_token_length(text: &str) -> Option<usize> {
+    url_length(text)
+        .or_else(|| email_length(text))
+        .or_else(|| dotted_number_length(text))
+        .or_else(|| identifier_length(text))
+}
+
+// "https://example.com/path?query", without the punctuation that ends a sentence
+fn url_length(text: &str) -> Option<usize> {
+    let scheme = ["http://", "https://", "www."].into_iter().find(|scheme| {
+        text.get(..scheme.len())
+            .is_some_and(|start| start.eq_ignore_ascii_case(scheme))
+    })?;
+
+    let end = text
+        .find(|c: char| c.is_whitespace() || matches!(c, '<' | '>' | '"'))
+        .unwrap_or(text.len());
+    let url = text[..end].trim_end_matches(['.', ',', ';', ':', '!', '?', ')', ']', '}', '\'']);
+
+    (url.len() > scheme.len()).then_some(url.len())
+}
+
+// "name.surname@example.com"
+fn email_length(text: &str) -> Option<usize> {
+    let local = text
+        .find(|c: char| !(c.is_alphanumeric() || matches!(c, '.' | '_' | '%' | '+' | '-')))
+        .unwrap_or(text.len());
+    if local == 0
+        || !text[..local].starts_with(char::is_alphanumeric)
+        || !text[local..].starts_with('@')
+    {
+        return None;
     }
+
+    let domain = &text[local + 1..];
+    let end = domain
+        .find(|c: char| !(c.is_alphanumeric() || matches!(c, '.' | '-')))
+        .unwrap_or(domain.len());
+    let domain = domain[..end].trim_end_matches(['.', '-']);
+
+    let (name, top_level) = domain.rsplit_once('.')?;
+    let valid = !name.is_empty()
+        && top_level.chars().count() >= 2
+        && top_level.chars().all(char::is_alphabetic);
+
+    valid.then_some(local + 1 + domain.len())
+}
+
+// Versions, IPs and decimals, "1.2.3", "192.168.0.1" and "10.25"
+fn dotted_number_length(text: &str) -> Option<usize> {
+    joined_length(text, '.', |c| c.is_ascii_digit())
+}
+
+// "snake_case_name", "x86_64"
+fn identifier_length(text: &str) -> Option<usize> {
+    joined_length(text, '_', char::is_alphanumeric)
+}
+
+// Runs of `part` characters joined by single separators, at least two of them
+fn joined_length(text: &str, separator: char, part: impl Fn(char) -> bool) -> Option<usize> {
+    let mut length = 0;
+    let mut parts = 0;
+
+    loop {
+        let rest = &text[length..];
+        let run = rest.find(|c| !part(c)).unwrap_or(rest.len());
+        if run == 0 {
+            break;
+        }
+
+        length += run;
+        parts += 1;
+
+        let rest = &text[length..];
+        let next_is_part = rest
+            .strip_prefix(separator)
+            .and_then(|rest| rest.chars().next())
+            .is_some_and(&part);
+        if !next_is_part {
+            break;
+        }
+        length += separator.len_utf8();
+    }
+
+    (parts > 1).then_some(length)
+}
+
+// Compatibility composed and case folded, so "Café", "CAFE\u{301}" and "café" are the same term.
+// Applied the same way to indexed and queried tokens
+pub fn normalize(token: &str) -> String {
+    let composed = token.nfkc().collect::<String>();
+    default_case_fold_str(&composed).nfkc().collect()
+}
+
+// How tokens are turned into terms, stored in the index so queries are analyzed the same way
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AnalysisSettings {
+    // English Snowball stemming, "indexing" and "indexed" become "index"
+    pub stemming: bool,
+    pub stop_words: StopWords,
+    // Length of the n-grams Chinese, Japanese and Korean text is split into, see `CjkNGrams`
+    pub cjk_ngrams: Option<usize>,
+    // "1.2.3", "10.25", URLs and emails as one term, their parts indexed with them
+    pub special_tokens: bool,
+    // Text of string literals in source code is not indexed
+    pub skip_strings: bool,
+    // Accents folded away as a variant, "resume" also finds "résumé"
+    pub fold_diacritics: bool,
+}
+
+pub struct Token {
+    pub text: String,
+    // Index of the content section the token is in
+    pub section: usize,
+    // Byte range inside the section
+    pub span: Range<usize>,
+    // Takes the position of the token before it, see `Term`
+    pub part: bool,
+}
+
+// Tokens of every section in document order, the index of a whole token is its position in the index
+pub fn tokenize_content<'c>(
+    content: &'c Content,
+    analyzer: &'c dyn Analyzer,
+) -> impl Iterator<Item = Token> + 'c {
+    content
+        .sections()
+        .iter()
+        .enumerate()
+        .flat_map(move |(section, (_, text))| {
+            analyzer.analyze(text).map(move |term| Token {
+                text: term.text,
+                section,
+                span: term.span,
+                part: term.part,
+            })
+        })
 }
 
 impl<'s> Iterator for Lexer<'s> {
-    type Item = String;
+    type Item = &'s str;
 
     fn next(&mut self) -> Option<Self::Item> {
         self.next_token()
@@ -59,55 +232,73 @@ impl<'s> Iterator for Lexer<'s> {
 
 #[cfg(test)]
 mod tests {
-    use super::Lexer;
-    use crate::file_types::read_xml_file;
-    const FILE_PATH: &'static str = "files/tokenize.html";
+    use super::{normalize, Lexer};
+    use crate::file_types::read_html_file;
+    const FILE_PATH: &str = "files/tokenize.html";
 
     #[test]
     fn lexeme() {
-        let content = read_xml_file(FILE_PATH).unwrap();
+        let content = read_html_file(FILE_PATH).unwrap();
+        let content = content
+            .sections()
+            .iter()
+            .map(|(_, section)| section.as_str())
+            .collect::<Vec<_>>()
+            .join(" ");
 
-        let char_slice = content.chars().collect::<Vec<_>>();
-        let mut lexer = Lexer::new(&char_slice);
+        let mut lexer = Lexer::new(&content);
 
         // LSERT
-        assert_eq!(lexer.next(), Some(String::from("LSERT")));
+        assert_eq!(lexer.next(), Some("LSERT"));
 
         // Hello, 20
-        assert_eq!(lexer.next(), Some(String::from("Hello")));
-        assert_eq!(lexer.next(), Some(String::from(",")));
-        assert_eq!(lexer.next(), Some(String::from("20")));
+        assert_eq!(lexer.next(), Some("Hello"));
+        assert_eq!(lexer.next(), Some(","));
+        assert_eq!(lexer.next(), Some("20"));
 
         // 20Hello
-        assert_eq!(lexer.next(), Some(String::from("20")));
-        assert_eq!(lexer.next(), Some(String::from("Hello")));
+        assert_eq!(lexer.next(), Some("20"));
+        assert_eq!(lexer.next(), Some("Hello"));
 
         // 20, 10
-        assert_eq!(lexer.next(), Some(String::from("20")));
-        assert_eq!(lexer.next(), Some(String::from(",")));
-        assert_eq!(lexer.next(), Some(String::from("10")));
+        assert_eq!(lexer.next(), Some("20"));
+        assert_eq!(lexer.next(), Some(","));
+        assert_eq!(lexer.next(), Some("10"));
 
         // [20, 10, 30]
-        assert_eq!(lexer.next(), Some(String::from("[")));
-        assert_eq!(lexer.next(), Some(String::from("20")));
-        assert_eq!(lexer.next(), Some(String::from(",")));
-        assert_eq!(lexer.next(), Some(String::from("10")));
-        assert_eq!(lexer.next(), Some(String::from(",")));
-        assert_eq!(lexer.next(), Some(String::from("30")));
-        assert_eq!(lexer.next(), Some(String::from("]")));
+        assert_eq!(lexer.next(), Some("["));
+        assert_eq!(lexer.next(), Some("20"));
+        assert_eq!(lexer.next(), Some(","));
+        assert_eq!(lexer.next(), Some("10"));
+        assert_eq!(lexer.next(), Some(","));
+        assert_eq!(lexer.next(), Some("30"));
+        assert_eq!(lexer.next(), Some("]"));
 
         // 2002, 20.10.20
-        assert_eq!(lexer.next(), Some(String::from("2002")));
-        assert_eq!(lexer.next(), Some(String::from(",")));
-        assert_eq!(lexer.next(), Some(String::from("20")));
-        assert_eq!(lexer.next(), Some(String::from(".")));
-        assert_eq!(lexer.next(), Some(String::from("10")));
-        assert_eq!(lexer.next(), Some(String::from(".")));
-        assert_eq!(lexer.next(), Some(String::from("20")));
+        assert_eq!(lexer.next(), Some("2002"));
+        assert_eq!(lexer.next(), Some(","));
+        assert_eq!(lexer.next(), Some("20"));
+        assert_eq!(lexer.next(), Some("."));
+        assert_eq!(lexer.next(), Some("10"));
+        assert_eq!(lexer.next(), Some("."));
+        assert_eq!(lexer.next(), Some("20"));
 
         // 10.2532
-        assert_eq!(lexer.next(), Some(String::from("10")));
-        assert_eq!(lexer.next(), Some(String::from(".")));
-        assert_eq!(lexer.next(), Some(String::from("2532")));
+        assert_eq!(lexer.next(), Some("10"));
+        assert_eq!(lexer.next(), Some("."));
+        assert_eq!(lexer.next(), Some("2532"));
+
+        // 10.fa
+        assert_eq!(lexer.next(), Some("10"));
+        assert_eq!(lexer.next(), Some("."));
+        assert_eq!(lexer.next(), Some("fa"));
+
+        // Rust RUST rust, Cafe\u{301} CAFÉ, ﬁle Straße
+        let normalized = lexer.map(normalize).collect::<Vec<_>>();
+        assert_eq!(
+            normalized,
+            vec!["rust", "rust", "rust", ",", "café", "café", ",", "file", "strasse"]
+        );
+        assert_eq!(normalize("Cafe\u{301}"), normalize("caf\u{e9}"));
     }
 }
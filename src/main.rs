@@ -1,8 +1,16 @@
+mod analyzer;
 mod args;
 mod file_types;
+mod fuzzy;
+mod index;
 mod io_control;
 mod lexical_analysis;
+mod query;
+mod ranking;
 mod serve;
+mod snippet;
+mod stop_words;
+mod trie;
 
 use args::Args;
 
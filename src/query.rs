@@ -0,0 +1,715 @@
+use crate::analyzer::Analyzer;
+use crate::index::Document;
+use serde::Serialize;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::ops::Range;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Clause {
+    Term(String),
+    // Terms that must appear next to each other, written in double quotes
+    Phrase(Vec<String>),
+}
+
+impl Clause {
+    // Words like "error-prone" are split by the Lexer, they are kept together as a phrase.
+    // None when every token is a stop word
+    fn from_terms(mut terms: Vec<String>) -> Option<Self> {
+        match terms.len() {
+            0 => None,
+            1 => terms.pop().map(Clause::Term),
+            _ => Some(Clause::Phrase(terms)),
+        }
+    }
+
+    // Number of tokens the clause spans
+    pub fn len(&self) -> usize {
+        match self {
+            Clause::Term(_) => 1,
+            Clause::Phrase(phrase) => phrase.len(),
+        }
+    }
+
+    // Positions the clause starts at in the document
+    pub fn occurrences(&self, document: &Document) -> Vec<usize> {
+        match self {
+            Clause::Term(term) => document.term_occurrences(term).to_vec(),
+            Clause::Phrase(phrase) => document.phrase_occurrences(phrase),
+        }
+    }
+
+    fn occurs_in(&self, document: &Document) -> bool {
+        match self {
+            Clause::Term(term) => !document.term_occurrences(term).is_empty(),
+            Clause::Phrase(phrase) => !document.phrase_occurrences(phrase).is_empty(),
+        }
+    }
+}
+
+//  Grammar:
+//      or      := and (["OR"] and)*
+//      and     := unary ("AND" unary)*
+//      unary   := ("+" | "-" | "NOT") unary | primary
+//      primary := "(" or ")" | "\"" phrase "\"" | term
+//
+//  Whitespace works like OR, so plain queries keep matching any of their terms
+#[derive(Clone, Debug, PartialEq)]
+pub enum Query {
+    Clause(Clause),
+    // `+term`, must match even when it is only one of the alternatives
+    Required(Box<Query>),
+    // `-term` and `NOT term`
+    Not(Box<Query>),
+    And(Vec<Query>),
+    Or(Vec<Query>),
+}
+
+impl Query {
+    // Terms are analyzed the same way as the index they are searched in, in the form every
+    // analyzer gives them, like "parse_query" which is a whole identifier in source code
+    // and the phrase "parse _ query" in text
+    pub fn parse(input: &str, analyzers: &[&dyn Analyzer]) -> Result<Self, ParseError> {
+        let tokens = tokenize(input)?;
+        let mut parser = Parser {
+            tokens,
+            cursor: 0,
+            end: input.chars().count(),
+            analyzers,
+        };
+
+        if parser.tokens.is_empty() {
+            return Err(ParseError::new("Provide a valid query!", 0));
+        }
+
+        let query = parser.parse_or()?;
+        if let Some((_, position)) = parser.peek() {
+            return Err(ParseError::new("Unexpected ')'", position));
+        }
+
+        let Some(query) = query else {
+            return Err(ParseError::new(
+                "The query only has stop words, add a term to search for",
+                0,
+            ));
+        };
+
+        if query.positive_clauses().is_empty() {
+            return Err(ParseError::new(
+                "The query only excludes terms, add a term to search for",
+                0,
+            ));
+        }
+
+        Ok(query)
+    }
+
+    // Clauses that contribute to the score, excluded ones are left out.
+    // A clause excluded twice, as in `NOT NOT rust`, is a positive one
+    pub fn positive_clauses(&self) -> Vec<&Clause> {
+        self.clauses(true)
+    }
+
+    fn clauses(&self, positive: bool) -> Vec<&Clause> {
+        match self {
+            Query::Clause(clause) if positive => vec![clause],
+            Query::Clause(_) => Vec::new(),
+            Query::Required(query) => query.clauses(positive),
+            Query::Not(query) => query.clauses(!positive),
+            Query::And(queries) | Query::Or(queries) => queries
+                .iter()
+                .flat_map(|query| query.clauses(positive))
+                .collect(),
+        }
+    }
+
+    // Rebuilds the query with every clause that is not excluded passed through `f`
+    pub fn map_clauses(self, f: &mut impl FnMut(Clause) -> Query) -> Query {
+        self.map_positive(true, f)
+    }
+
+    fn map_positive(self, positive: bool, f: &mut impl FnMut(Clause) -> Query) -> Query {
+        match self {
+            Query::Clause(clause) if positive => f(clause),
+            Query::Clause(clause) => Query::Clause(clause),
+            Query::Required(query) => Query::Required(Box::new(query.map_positive(positive, f))),
+            Query::Not(query) => Query::Not(Box::new(query.map_positive(!positive, f))),
+            Query::And(queries) => Query::And(
+                queries
+                    .into_iter()
+                    .map(|query| query.map_positive(positive, f))
+                    .collect(),
+            ),
+            Query::Or(queries) => Query::Or(
+                queries
+                    .into_iter()
+                    .map(|query| query.map_positive(positive, f))
+                    .collect(),
+            ),
+        }
+    }
+
+    fn fmt_nested(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Query::And(_) | Query::Or(_) => write!(f, "({self})"),
+            _ => write!(f, "{self}"),
+        }
+    }
+
+    pub fn matches(&self, document: &Document) -> bool {
+        match self {
+            Query::Clause(clause) => clause.occurs_in(document),
+            Query::Required(query) => query.matches(document),
+            Query::Not(query) => !query.matches(document),
+            Query::And(queries) => queries.iter().all(|query| query.matches(document)),
+            Query::Or(queries) => {
+                let mut required = false;
+                let mut optional = false;
+                let mut optional_match = false;
+
+                for query in queries {
+                    match query {
+                        Query::Required(inner) => {
+                            if !inner.matches(document) {
+                                return false;
+                            }
+                            required = true;
+                        }
+                        Query::Not(inner) => {
+                            if inner.matches(document) {
+                                return false;
+                            }
+                        }
+                        query => {
+                            optional = true;
+                            optional_match = optional_match || query.matches(document);
+                        }
+                    }
+                }
+
+                required || !optional || optional_match
+            }
+        }
+    }
+}
+
+// Written back in the query syntax, so it can be sent again
+impl Display for Query {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Query::Clause(Clause::Term(term)) => write!(f, "{term}"),
+            Query::Clause(Clause::Phrase(phrase)) => write!(f, "\"{}\"", phrase.join(" ")),
+            Query::Required(query) => {
+                write!(f, "+")?;
+                query.fmt_nested(f)
+            }
+            Query::Not(query) => {
+                write!(f, "-")?;
+                query.fmt_nested(f)
+            }
+            Query::And(queries) => {
+                for (i, query) in queries.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, " AND ")?;
+                    }
+                    match query {
+                        Query::Or(_) => query.fmt_nested(f)?,
+                        _ => write!(f, "{query}")?,
+                    }
+                }
+                Ok(())
+            }
+            Query::Or(queries) => {
+                for (i, query) in queries.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, " ")?;
+                    }
+                    match query {
+                        Query::Or(_) => query.fmt_nested(f)?,
+                        _ => write!(f, "{query}")?,
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct ParseError {
+    pub message: String,
+    // Character offset in the query
+    pub position: usize,
+}
+
+impl ParseError {
+    fn new(message: &str, position: usize) -> Self {
+        Self {
+            message: message.to_string(),
+            position,
+        }
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{} (at {})", self.message, self.position)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    Word(String),
+    Phrase(String),
+    Open,
+    Close,
+    Plus,
+    Minus,
+    And,
+    Or,
+    Not,
+}
+
+fn is_word_end(c: char) -> bool {
+    c.is_whitespace() || c == '(' || c == ')' || c == '"'
+}
+
+fn tokenize(input: &str) -> Result<Vec<(Token, usize)>, ParseError> {
+    let chars = input.chars().collect::<Vec<_>>();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let start = i;
+        let token = match chars[i] {
+            c if c.is_whitespace() => {
+                i += 1;
+                continue;
+            }
+            '(' => {
+                i += 1;
+                Token::Open
+            }
+            ')' => {
+                i += 1;
+                Token::Close
+            }
+            '"' => {
+                let length = chars[i + 1..].iter().position(|&c| c == '"');
+                let Some(length) = length else {
+                    return Err(ParseError::new("Missing closing '\"'", start));
+                };
+
+                i += length + 2;
+                Token::Phrase(chars[start + 1..start + 1 + length].iter().collect())
+            }
+            // A lone sign is searched for as it is, before a word, a phrase or a group it is an operator
+            c @ ('+' | '-')
+                if chars
+                    .get(i + 1)
+                    .is_some_and(|&n| !n.is_whitespace() && n != ')') =>
+            {
+                i += 1;
+                if c == '+' {
+                    Token::Plus
+                } else {
+                    Token::Minus
+                }
+            }
+            _ => {
+                while i < chars.len() && !is_word_end(chars[i]) {
+                    i += 1;
+                }
+
+                let word = chars[start..i].iter().collect::<String>();
+                match word.as_str() {
+                    "AND" => Token::And,
+                    "OR" => Token::Or,
+                    "NOT" => Token::Not,
+                    _ => Token::Word(word),
+                }
+            }
+        };
+
+        tokens.push((token, start));
+    }
+
+    Ok(tokens)
+}
+
+// Every term of the query with the byte range of the input it comes from, in the form of
+// every analyzer, so words can be swapped in the query as the user wrote it
+pub fn term_spans(input: &str, analyzers: &[&dyn Analyzer]) -> Vec<(String, Range<usize>)> {
+    let Ok(tokens) = tokenize(input) else {
+        return Vec::new();
+    };
+    // Tokens are at character positions
+    let byte_at = |position: usize| {
+        input
+            .char_indices()
+            .nth(position)
+            .map_or(input.len(), |(byte, _)| byte)
+    };
+
+    let mut spans = Vec::new();
+    for (token, position) in tokens {
+        let (text, start) = match &token {
+            Token::Word(text) => (text, byte_at(position)),
+            Token::Phrase(text) => (text, byte_at(position) + '"'.len_utf8()),
+            _ => continue,
+        };
+
+        for analyzer in analyzers {
+            for term in analyzer.analyze(text) {
+                spans.push((term.text, start + term.span.start..start + term.span.end));
+            }
+        }
+    }
+
+    spans
+}
+
+// The clause of the text, or of either form of it when its terms have variants.
+// The exact form matches both, so its documents rank higher
+fn query_from_text(text: &str, analyzer: &dyn Analyzer) -> Option<Query> {
+    let mut exact = Vec::new();
+    let mut variants = Vec::new();
+
+    for term in analyzer.analyze(text) {
+        if term.variant {
+            variants.pop();
+            variants.push(term.text);
+        } else if !term.part {
+            // Parts are only indexed, "1.2.3" is searched for as a whole
+            exact.push(term.text.clone());
+            variants.push(term.text);
+        }
+    }
+
+    let has_variants = variants != exact;
+    let exact = Clause::from_terms(exact)?;
+    match Clause::from_terms(variants) {
+        Some(variants) if has_variants => Some(Query::Or(vec![
+            Query::Clause(exact),
+            Query::Clause(variants),
+        ])),
+        _ => Some(Query::Clause(exact)),
+    }
+}
+
+// Either of the distinct clauses of the text, one for every analyzer
+fn query_from_forms(text: &str, analyzers: &[&dyn Analyzer]) -> Option<Query> {
+    let mut forms = Vec::new();
+    for analyzer in analyzers {
+        if let Some(query) = query_from_text(text, *analyzer) {
+            if !forms.contains(&query) {
+                forms.push(query);
+            }
+        }
+    }
+
+    match forms.len() {
+        0 | 1 => forms.pop(),
+        _ => Some(Query::Or(forms)),
+    }
+}
+
+struct Parser<'s> {
+    tokens: Vec<(Token, usize)>,
+    cursor: usize,
+    // Position reported for errors at the end of the query
+    end: usize,
+    analyzers: &'s [&'s dyn Analyzer],
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<(&Token, usize)> {
+        self.tokens
+            .get(self.cursor)
+            .map(|(token, position)| (token, *position))
+    }
+
+    // Stop words are left out of the query, None when nothing else is left
+    fn parse_or(&mut self) -> Result<Option<Query>, ParseError> {
+        let mut queries = Vec::from_iter(self.parse_and()?);
+
+        while let Some((token, _)) = self.peek() {
+            match token {
+                Token::Close => break,
+                Token::Or => {
+                    self.cursor += 1;
+                    queries.extend(self.parse_and()?);
+                }
+                _ => queries.extend(self.parse_and()?),
+            }
+        }
+
+        Ok(match queries.len() {
+            0 => None,
+            1 => queries.pop(),
+            _ => Some(Query::Or(queries)),
+        })
+    }
+
+    fn parse_and(&mut self) -> Result<Option<Query>, ParseError> {
+        let mut queries = Vec::from_iter(self.parse_unary()?);
+
+        while let Some((Token::And, _)) = self.peek() {
+            self.cursor += 1;
+            queries.extend(self.parse_unary()?);
+        }
+
+        Ok(match queries.len() {
+            0 => None,
+            1 => queries.pop(),
+            _ => Some(Query::And(queries)),
+        })
+    }
+
+    fn parse_unary(&mut self) -> Result<Option<Query>, ParseError> {
+        match self.peek() {
+            Some((Token::Plus, _)) => {
+                self.cursor += 1;
+                let query = self.parse_unary()?;
+                Ok(query.map(|query| Query::Required(Box::new(query))))
+            }
+            Some((Token::Minus, _)) | Some((Token::Not, _)) => {
+                self.cursor += 1;
+                let query = self.parse_unary()?;
+                Ok(query.map(|query| Query::Not(Box::new(query))))
+            }
+            _ => self.parse_primary(),
+        }
+    }
+
+    fn parse_primary(&mut self) -> Result<Option<Query>, ParseError> {
+        let Some((token, position)) = self.peek() else {
+            return Err(ParseError::new("Expected a term", self.end));
+        };
+
+        let query = match token {
+            Token::Open => {
+                self.cursor += 1;
+                if let Some((Token::Close, close)) = self.peek() {
+                    return Err(ParseError::new("Empty parentheses", close));
+                }
+
+                let query = self.parse_or()?;
+                match self.peek() {
+                    Some((Token::Close, _)) => query,
+                    _ => return Err(ParseError::new("Missing closing ')'", position)),
+                }
+            }
+            Token::Phrase(text) if text.trim().is_empty() => {
+                return Err(ParseError::new("Empty phrase", position))
+            }
+            Token::Word(text) | Token::Phrase(text) => query_from_forms(text, self.analyzers),
+            Token::Close => return Err(ParseError::new("Unexpected ')'", position)),
+            Token::And | Token::Or => {
+                return Err(ParseError::new(
+                    "Expected a term before the operator",
+                    position,
+                ))
+            }
+            Token::Plus | Token::Minus | Token::Not => unreachable!(),
+        };
+
+        self.cursor += 1;
+        Ok(query)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Clause, ParseError, Query};
+    use crate::analyzer::{Analyzers, Pipeline, Words};
+    use crate::file_types::{Content, Field};
+    use crate::index::Document;
+    use crate::lexical_analysis::{tokenize_content, AnalysisSettings};
+    use std::path::Path;
+
+    fn parse(input: &str) -> Result<Query, ParseError> {
+        Query::parse(input, &[&AnalysisSettings::default().analyzer()])
+    }
+
+    fn term(term: &str) -> Query {
+        Query::Clause(Clause::Term(term.to_string()))
+    }
+
+    fn indexed(analyzers: &Analyzers, path: &str, text: &str) -> Document {
+        let mut content = Content::new();
+        content.push(Field::Body, text);
+
+        let mut document = Document::default();
+        for token in tokenize_content(&content, analyzers.for_path(Path::new(path))) {
+            if token.part {
+                document.push_part(token.text);
+            } else {
+                document.push(token.text);
+            }
+        }
+        document
+    }
+
+    fn phrase(terms: &[&str]) -> Query {
+        Query::Clause(Clause::Phrase(
+            terms.iter().map(|t| t.to_string()).collect(),
+        ))
+    }
+
+    fn document(tokens: &[&str]) -> Document {
+        let mut document = Document::default();
+        for token in tokens {
+            document.push(token.to_string());
+        }
+        document
+    }
+
+    #[test]
+    fn boolean_grammar() {
+        let query = parse("rust \"error handling\" OR (a AND NOT b) +c -d").unwrap();
+
+        assert_eq!(
+            query,
+            Query::Or(vec![
+                term("rust"),
+                phrase(&["error", "handling"]),
+                Query::And(vec![term("a"), Query::Not(Box::new(term("b")))]),
+                Query::Required(Box::new(term("c"))),
+                Query::Not(Box::new(term("d"))),
+            ])
+        );
+
+        // Signs inside a word are not operators
+        assert_eq!(
+            parse("error-prone").unwrap(),
+            phrase(&["error", "-", "prone"])
+        );
+    }
+
+    #[test]
+    fn parse_errors() {
+        let error = |message: &str, position| {
+            Err(ParseError {
+                message: message.to_string(),
+                position,
+            })
+        };
+
+        assert_eq!(parse("(rust AND"), error("Expected a term", 9));
+        assert_eq!(parse("(rust"), error("Missing closing ')'", 0));
+        assert_eq!(parse("rust)"), error("Unexpected ')'", 4));
+        assert_eq!(parse("a \"b"), error("Missing closing '\"'", 2));
+        assert!(parse("-rust").is_err());
+        assert!(parse("NOT NOT -rust").is_err());
+    }
+
+    #[test]
+    fn stop_words() {
+        let settings = AnalysisSettings {
+            stop_words: "punctuation,english".parse().unwrap(),
+            ..AnalysisSettings::default()
+        };
+        let analyzer = settings.analyzer();
+        let parse = |input| Query::parse(input, &[&analyzer]);
+
+        assert_eq!(parse("the rust AND -a").unwrap(), term("rust"));
+        assert_eq!(
+            parse("\"state of the art\"").unwrap(),
+            phrase(&["state", "art"])
+        );
+        assert!(parse("the , a").is_err());
+    }
+
+    #[test]
+    fn diacritic_variants() {
+        let settings = AnalysisSettings {
+            fold_diacritics: true,
+            ..AnalysisSettings::default()
+        };
+        let analyzer = settings.analyzer();
+        let parse = |input| Query::parse(input, &[&analyzer]).unwrap();
+
+        assert_eq!(
+            parse("Résumé"),
+            Query::Or(vec![term("résumé"), term("resume")])
+        );
+        assert_eq!(parse("resume"), term("resume"));
+        assert_eq!(
+            parse("\"München city\""),
+            Query::Or(vec![
+                phrase(&["münchen", "city"]),
+                phrase(&["munchen", "city"])
+            ])
+        );
+    }
+
+    #[test]
+    fn required_and_excluded_terms() {
+        let documents = [
+            document(&["rust", "error", "handling"]),
+            document(&["rust", "guide"]),
+            document(&["handling", "error"]),
+        ];
+
+        let matches = |query: &str| {
+            let query = parse(query).unwrap();
+            documents
+                .iter()
+                .map(|document| query.matches(document))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(matches("\"error handling\""), vec![true, false, false]);
+        assert_eq!(matches("guide +error"), vec![true, false, true]);
+        assert_eq!(matches("rust -guide"), vec![true, false, false]);
+        assert_eq!(
+            matches("rust -\"error handling\""),
+            vec![false, true, false]
+        );
+        assert_eq!(matches("rust -(guide OR error)"), vec![false, false, false]);
+        assert_eq!(matches("handling -(rust guide)"), vec![false, false, true]);
+        assert_eq!(
+            matches("guide +\"handling error\""),
+            vec![false, false, true]
+        );
+        assert_eq!(matches("error AND NOT rust"), vec![false, false, true]);
+        assert_eq!(matches("NOT NOT rust"), vec![true, true, false]);
+        assert_eq!(
+            matches("(guide OR handling) AND rust"),
+            vec![true, true, false]
+        );
+    }
+
+    #[test]
+    fn identifiers_in_source_and_text() {
+        let analyzers = Analyzers::new(&AnalysisSettings::default());
+        let source = indexed(&analyzers, "query.rs", "fn parse_query(input: &str) {}");
+        let notes = indexed(&analyzers, "notes.txt", "Call parse_query with the input");
+        let other = indexed(&analyzers, "other.txt", "parse the query");
+
+        let query = Query::parse("parse_query", &analyzers.query()).unwrap();
+        assert!(query.matches(&source));
+        assert!(query.matches(&notes));
+        assert!(!query.matches(&other));
+
+        // Sub-words of an identifier are still found on their own
+        let query = Query::parse("query", &analyzers.query()).unwrap();
+        assert!(query.matches(&source));
+    }
+
+    #[test]
+    fn registered_analyzers_are_searched_with() {
+        // Case sensitive file type, its terms are never lowercased
+        let mut analyzers = Analyzers::new(&AnalysisSettings::default());
+        analyzers.register(&["log"], Pipeline::new(Words::default()));
+        let log = indexed(&analyzers, "server.log", "ERROR Timeout");
+        let notes = indexed(&analyzers, "notes.txt", "ERROR Timeout");
+
+        let query = Query::parse("ERROR", &analyzers.query()).unwrap();
+        assert!(query.matches(&log));
+        assert!(query.matches(&notes));
+        assert!(!Query::parse("ERROR", &[analyzers.query()[0]])
+            .unwrap()
+            .matches(&log));
+    }
+}
@@ -0,0 +1,503 @@
+use crate::file_types::Field;
+use crate::index::Index;
+use crate::query::{Clause, Query};
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+mod default {
+    pub(crate) const K1: f64 = 1.2;
+    pub(crate) const B: f64 = 0.75;
+    pub(crate) const TITLE_BOOST: f64 = 3.0;
+    pub(crate) const HEADING_BOOST: f64 = 2.0;
+    pub(crate) const PATH_BOOST: f64 = 2.0;
+    pub(crate) const DESCRIPTION_BOOST: f64 = 2.0;
+    pub(crate) const KEYWORDS_BOOST: f64 = 2.0;
+    // Score of a document with all query clauses next to each other is multiplied by 1 + this
+    pub(crate) const PROXIMITY_WEIGHT: f64 = 1.0;
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Ranker {
+    #[default]
+    TfIdf,
+    // k1: term frequency saturation, b: document length normalization
+    Bm25 {
+        k1: f64,
+        b: f64,
+    },
+}
+
+impl FromStr for Ranker {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "tfidf" | "tf-idf" => Ok(Ranker::TfIdf),
+            "bm25" => Ok(Ranker::Bm25 {
+                k1: default::K1,
+                b: default::B,
+            }),
+            _ => Err(format!("{s:?} is not a valid ranker")),
+        }
+    }
+}
+
+impl Ranker {
+    // Overrides the BM25 parameters that are given, other rankers are left untouched
+    pub fn tune(self, k1: Option<f64>, b: Option<f64>) -> Self {
+        match self {
+            Ranker::Bm25 {
+                k1: default_k1,
+                b: default_b,
+            } => Ranker::Bm25 {
+                k1: k1.unwrap_or(default_k1),
+                b: b.unwrap_or(default_b),
+            },
+            ranker => ranker,
+        }
+    }
+
+    fn weight(&self, count: f64, df: usize, length: usize, corpus: &Corpus) -> f64 {
+        match *self {
+            Ranker::TfIdf => term_frequency(count, length) * inverse_document_frequency(df, corpus),
+            Ranker::Bm25 { k1, b } => bm25(count, df, length, corpus, k1, b),
+        }
+    }
+}
+
+// Weight of a single occurrence in every field
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FieldBoosts {
+    pub title: f64,
+    pub heading: f64,
+    pub body: f64,
+    pub path: f64,
+    pub description: f64,
+    pub keywords: f64,
+    pub alt: f64,
+    pub tooltip: f64,
+    pub link: f64,
+}
+
+impl Default for FieldBoosts {
+    fn default() -> Self {
+        Self {
+            title: default::TITLE_BOOST,
+            heading: default::HEADING_BOOST,
+            body: 1.0,
+            path: default::PATH_BOOST,
+            description: default::DESCRIPTION_BOOST,
+            keywords: default::KEYWORDS_BOOST,
+            alt: 1.0,
+            tooltip: 1.0,
+            link: 1.0,
+        }
+    }
+}
+
+impl FieldBoosts {
+    pub fn get(&self, field: Field) -> f64 {
+        match field {
+            Field::Title => self.title,
+            Field::Heading => self.heading,
+            Field::Body => self.body,
+            Field::Path => self.path,
+            Field::Description => self.description,
+            Field::Keywords => self.keywords,
+            Field::Alt => self.alt,
+            Field::Tooltip => self.tooltip,
+            Field::Link => self.link,
+        }
+    }
+
+    pub fn set(&mut self, field: Field, boost: f64) {
+        match field {
+            Field::Title => self.title = boost,
+            Field::Heading => self.heading = boost,
+            Field::Body => self.body = boost,
+            Field::Path => self.path = boost,
+            Field::Description => self.description = boost,
+            Field::Keywords => self.keywords = boost,
+            Field::Alt => self.alt = boost,
+            Field::Tooltip => self.tooltip = boost,
+            Field::Link => self.link = boost,
+        }
+    }
+}
+
+// Overrides of the default boosts, as in "title:3,heading:1.5"
+impl FromStr for FieldBoosts {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut boosts = FieldBoosts::default();
+
+        for pair in s.split(',') {
+            let Some((field, boost)) = pair.split_once(':') else {
+                return Err(format!("{pair:?} is not a valid <field>:<boost> pair"));
+            };
+
+            let field = field.trim().parse::<Field>()?;
+            let boost = boost
+                .trim()
+                .parse::<f64>()
+                .map_err(|_| format!("{boost:?} is not a valid boost"))?;
+            boosts.set(field, boost);
+        }
+
+        Ok(boosts)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Scoring {
+    pub ranker: Ranker,
+    pub boosts: FieldBoosts,
+    // Documents with the query clauses close together score higher
+    pub proximity: bool,
+}
+
+impl Scoring {
+    // Only documents matching the query are scored, excluded clauses never add to the score
+    pub fn rank(&self, query: &Query, index: &Index) -> Vec<(PathBuf, f64)> {
+        let clauses = query.positive_clauses();
+        let corpus = Corpus {
+            document_count: index.documents.len(),
+            average_length: index.average_length(),
+        };
+
+        // Boosted clause counts of the matching documents, and the number of documents every clause is in
+        let mut matches = Vec::new();
+        let mut df = vec![0; clauses.len()];
+        for (path, document) in &index.documents {
+            let occurrences = clauses
+                .iter()
+                .map(|clause| clause.occurrences(document))
+                .collect::<Vec<_>>();
+
+            for (n, positions) in df.iter_mut().zip(&occurrences) {
+                if !positions.is_empty() {
+                    *n += 1;
+                }
+            }
+
+            if query.matches(document) {
+                let counts = occurrences
+                    .iter()
+                    .map(|positions| {
+                        positions
+                            .iter()
+                            .map(|&position| self.boosts.get(document.field_at(position)))
+                            .sum::<f64>()
+                    })
+                    .collect::<Vec<_>>();
+
+                let factor = if self.proximity {
+                    1.0 + default::PROXIMITY_WEIGHT * proximity(&clauses, &occurrences)
+                } else {
+                    1.0
+                };
+
+                matches.push((path, document.length, counts, factor));
+            }
+        }
+
+        matches
+            .into_iter()
+            .map(|(path, length, counts, factor)| {
+                let score = counts
+                    .iter()
+                    .zip(&df)
+                    .map(|(&count, &df)| self.ranker.weight(count, df, length, &corpus))
+                    .sum::<f64>();
+
+                (path.clone(), score * factor)
+            })
+            .collect()
+    }
+}
+
+// Between 0 and 1, 1 when the clauses found in the document sit right next to each other
+fn proximity(clauses: &[&Clause], occurrences: &[Vec<usize>]) -> f64 {
+    let found = occurrences
+        .iter()
+        .enumerate()
+        .filter(|(_, positions)| !positions.is_empty())
+        .map(|(i, _)| i)
+        .collect::<Vec<_>>();
+
+    if found.len() < 2 {
+        return 0.0;
+    }
+
+    let ideal = found.iter().map(|&i| clauses[i].len()).sum::<usize>();
+    match minimal_span(clauses, occurrences, &found) {
+        Some(span) => (ideal as f64 / span as f64).min(1.0),
+        None => 0.0,
+    }
+}
+
+// Fewest tokens a window needs to hold one occurrence of every found clause
+fn minimal_span(clauses: &[&Clause], occurrences: &[Vec<usize>], found: &[usize]) -> Option<usize> {
+    let mut events = found
+        .iter()
+        .enumerate()
+        .flat_map(|(slot, &i)| occurrences[i].iter().map(move |&position| (position, slot)))
+        .collect::<Vec<_>>();
+    events.sort_unstable();
+
+    // Occurrences of every clause inside the window, and the clauses with at least one
+    let mut inside = vec![0; found.len()];
+    let mut covered = 0;
+    let mut best: Option<usize> = None;
+    let mut first = 0;
+
+    for last in 0..events.len() {
+        let (_, slot) = events[last];
+        inside[slot] += 1;
+        if inside[slot] == 1 {
+            covered += 1;
+        }
+
+        while covered == found.len() {
+            // Ends at the last occurrence, a longer phrase earlier in the window is not accounted for
+            let (position, slot) = events[last];
+            let span = position + clauses[found[slot]].len() - events[first].0;
+            best = Some(best.map_or(span, |best| best.min(span)));
+
+            let (_, slot) = events[first];
+            inside[slot] -= 1;
+            if inside[slot] == 0 {
+                covered -= 1;
+            }
+            first += 1;
+        }
+    }
+
+    best
+}
+
+struct Corpus {
+    document_count: usize,
+    average_length: f64,
+}
+
+struct Scored(PathBuf, f64);
+
+impl PartialEq for Scored {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Scored {}
+
+impl PartialOrd for Scored {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+// Ties are broken by path, so pages stay stable between requests
+impl Ord for Scored {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.1
+            .total_cmp(&other.1)
+            .then_with(|| other.0.cmp(&self.0))
+    }
+}
+
+// Best `k` documents in descending score order, the heap never holds more than `k` entries
+pub fn top_k(documents: Vec<(PathBuf, f64)>, k: usize) -> Vec<(PathBuf, f64)> {
+    let mut heap = BinaryHeap::with_capacity(k.min(documents.len()) + 1);
+
+    for (path, score) in documents {
+        heap.push(Reverse(Scored(path, score)));
+        if heap.len() > k {
+            heap.pop();
+        }
+    }
+
+    heap.into_sorted_vec()
+        .into_iter()
+        .map(|Reverse(Scored(path, score))| (path, score))
+        .collect()
+}
+
+// Count normalized by the document length, so long files do not win by size alone
+fn term_frequency(count: f64, length: usize) -> f64 {
+    if length == 0 {
+        0.0
+    } else {
+        count / length as f64
+    }
+}
+
+// Smoothed, so a term found in every document still weighs a little above zero
+fn inverse_document_frequency(df: usize, corpus: &Corpus) -> f64 {
+    if df == 0 {
+        0.0
+    } else {
+        (1.0 + corpus.document_count as f64 / df as f64).ln()
+    }
+}
+
+fn bm25(count: f64, df: usize, length: usize, corpus: &Corpus, k1: f64, b: f64) -> f64 {
+    if count == 0.0 {
+        return 0.0;
+    }
+
+    let n = df as f64;
+    let idf = (1.0 + (corpus.document_count as f64 - n + 0.5) / (n + 0.5)).ln();
+
+    let length_ratio = if corpus.average_length == 0.0 {
+        1.0
+    } else {
+        length as f64 / corpus.average_length
+    };
+
+    idf * (count * (k1 + 1.0)) / (count + k1 * (1.0 - b + b * length_ratio))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{top_k, FieldBoosts, Scoring};
+    use crate::file_types::Field;
+    use crate::index::{Document, Index};
+    use crate::query::Query;
+    use std::path::PathBuf;
+
+    fn document(terms: &[(&str, usize)]) -> Document {
+        let mut document = Document::default();
+        for (term, count) in terms {
+            for _ in 0..*count {
+                document.push(term.to_string());
+            }
+        }
+        document
+    }
+
+    fn best_match(scoring: Scoring, index: &Index, query: &str) -> PathBuf {
+        let query = Query::parse(query, &[&index.settings.analyzer()]).unwrap();
+        let mut scores = scoring.rank(&query, index);
+        scores.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+        scores.remove(0).0
+    }
+
+    #[test]
+    fn rare_terms_outweigh_common_terms() {
+        let mut index = Index::default();
+        index.insert(
+            PathBuf::from("common"),
+            document(&[("the", 50), ("rust", 1)]),
+        );
+        index.insert(PathBuf::from("rare"), document(&[("the", 2), ("rust", 2)]));
+        index.insert(PathBuf::from("other"), document(&[("the", 5)]));
+
+        let query = "the rust";
+        let bm25 = Scoring {
+            ranker: "bm25".parse().unwrap(),
+            ..Scoring::default()
+        };
+        assert_eq!(
+            best_match(Scoring::default(), &index, query),
+            PathBuf::from("rare")
+        );
+        assert_eq!(best_match(bm25, &index, query), PathBuf::from("rare"));
+    }
+
+    #[test]
+    fn title_matches_are_boosted() {
+        let mut titled = Document::default();
+        titled.start_field(Field::Title);
+        titled.push(String::from("rust"));
+        titled.start_field(Field::Body);
+        titled.push(String::from("guide"));
+
+        let mut index = Index::default();
+        index.insert(PathBuf::from("titled"), titled);
+        index.insert(
+            PathBuf::from("body"),
+            document(&[("rust", 1), ("guide", 1)]),
+        );
+
+        assert_eq!(
+            best_match(Scoring::default(), &index, "rust"),
+            PathBuf::from("titled")
+        );
+
+        let flat = Scoring {
+            boosts: "title:0.5".parse::<FieldBoosts>().unwrap(),
+            ..Scoring::default()
+        };
+        assert_eq!(best_match(flat, &index, "rust"), PathBuf::from("body"));
+    }
+
+    #[test]
+    fn marked_words_are_boosted_inside_their_section() {
+        let mut linked = Document::default();
+        for token in ["read", "the", "safety", "notes"] {
+            linked.push(token.to_string());
+            if token == "safety" {
+                linked.mark(Field::Link);
+            }
+        }
+
+        let mut index = Index::default();
+        index.insert(PathBuf::from("linked"), linked);
+        index.insert(
+            PathBuf::from("body"),
+            document(&[("read", 1), ("the", 1), ("safety", 1), ("notes", 1)]),
+        );
+
+        let links = Scoring {
+            boosts: "link:3".parse::<FieldBoosts>().unwrap(),
+            ..Scoring::default()
+        };
+        assert_eq!(best_match(links, &index, "safety"), PathBuf::from("linked"));
+        // A phrase runs across the link
+        let query = Query::parse("\"the safety notes\"", &[&index.settings.analyzer()]).unwrap();
+        assert_eq!(links.rank(&query, &index).len(), 2);
+    }
+
+    #[test]
+    fn top_k_keeps_best_scores_in_order() {
+        let documents = (0..50)
+            .map(|i| (PathBuf::from(i.to_string()), ((i * 37) % 50) as f64))
+            .collect::<Vec<_>>();
+
+        let best = top_k(documents, 3);
+        let scores = best.iter().map(|(_, s)| *s).collect::<Vec<_>>();
+
+        assert_eq!(scores, vec![49.0, 48.0, 47.0]);
+    }
+
+    #[test]
+    fn close_terms_rank_higher_with_proximity() {
+        let mut index = Index::default();
+        index.insert(
+            PathBuf::from("close"),
+            document(&[("error", 1), ("handling", 1), ("filler", 10)]),
+        );
+
+        let mut far = Document::default();
+        for token in ["error", "filler", "filler", "filler", "filler", "filler"] {
+            far.push(token.to_string());
+        }
+        for token in ["filler", "filler", "filler", "filler", "filler", "handling"] {
+            far.push(token.to_string());
+        }
+        index.insert(PathBuf::from("far"), far);
+
+        let proximity = Scoring {
+            proximity: true,
+            ..Scoring::default()
+        };
+        assert_eq!(
+            best_match(proximity, &index, "error handling"),
+            PathBuf::from("close")
+        );
+    }
+}
@@ -1,12 +1,173 @@
-use crate::lexical_analysis::Lexer;
+use crate::analyzer::Analyzers;
+use crate::file_types::{reader_for, split_chapter, Field};
+use crate::fuzzy::{self, Correction};
+use crate::index::Index;
+use crate::lexical_analysis::normalize;
+use crate::query::{term_spans, ParseError, Query};
+use crate::ranking::{top_k, FieldBoosts, Ranker, Scoring};
+use crate::snippet::{snippets, Snippet};
+use crate::trie::Trie;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs;
-use std::io::{self, Error, ErrorKind};
+use std::io;
 use std::path::{Path, PathBuf};
 use tiny_http::{Header, Method, Request, Response, StatusCode};
 
-const WEB_FILE_DIR: &'static str = "files/web_files";
+const WEB_FILE_DIR: &str = "files/web_files";
+const DEFAULT_LIMIT: usize = 20;
+const DEFAULT_COMPLETIONS: usize = 10;
+// Completions are a short list under the search box, larger limits are cut down to it
+const MAX_COMPLETIONS: usize = 100;
 
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SearchBody {
+    Query(String),
+    Request(SearchRequest),
+}
+
+#[derive(Deserialize)]
+struct SearchRequest {
+    query: String,
+    ranker: Option<String>,
+    k1: Option<f64>,
+    b: Option<f64>,
+    boosts: Option<HashMap<Field, f64>>,
+    proximity: Option<bool>,
+    // Misspelled terms are searched for as their closest indexed terms unless disabled
+    fuzzy: Option<bool>,
+    // Text around the matches is sent with every result unless disabled
+    snippets: Option<bool>,
+    limit: Option<usize>,
+    offset: Option<usize>,
+}
+
+#[derive(Serialize)]
+struct SearchResult {
+    path: PathBuf,
+    score: f64,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    snippets: Vec<Snippet>,
+}
+
+#[derive(Serialize)]
+struct SearchResponse {
+    // Number of matching documents, not only the ones in this page
+    total: usize,
+    offset: usize,
+    limit: usize,
+    results: Vec<SearchResult>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    did_you_mean: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    warnings: Vec<String>,
+}
+
+enum SearchError {
+    // Malformed body or unknown options
+    Request(String),
+    Parse(ParseError),
+}
+
+#[derive(Serialize)]
+struct ErrorResponse {
+    error: ErrorBody,
+}
+
+#[derive(Serialize)]
+struct ErrorBody {
+    kind: &'static str,
+    message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    position: Option<usize>,
+}
+
+#[derive(Serialize)]
+struct Completion {
+    term: String,
+    frequency: usize,
+}
+
+#[derive(Serialize)]
+struct CompleteResponse {
+    prefix: String,
+    completions: Vec<Completion>,
+}
+
+impl SearchError {
+    fn status_code(&self) -> u16 {
+        match self {
+            SearchError::Request(_) | SearchError::Parse(_) => 400,
+        }
+    }
+
+    fn to_response(&self) -> ErrorResponse {
+        let (kind, message, position) = match self {
+            SearchError::Request(message) => ("request", message.clone(), None),
+            SearchError::Parse(err) => ("parse", err.message.clone(), Some(err.position)),
+        };
+
+        ErrorResponse {
+            error: ErrorBody {
+                kind,
+                message,
+                position,
+            },
+        }
+    }
+}
+
+impl SearchRequest {
+    //  Possible Errors:
+    //      Deserialize: Request
+    fn from_body(body_data: &str) -> Result<Self, SearchError> {
+        let body = serde_json::from_str::<SearchBody>(body_data)
+            .map_err(|err| SearchError::Request(err.to_string()))?;
+
+        Ok(match body {
+            SearchBody::Query(query) => SearchRequest {
+                query,
+                ranker: None,
+                k1: None,
+                b: None,
+                boosts: None,
+                proximity: None,
+                fuzzy: None,
+                snippets: None,
+                limit: None,
+                offset: None,
+            },
+            SearchBody::Request(request) => request,
+        })
+    }
+
+    // Falls back to the ranker the server was started with
+    fn ranker(&self, default: Ranker) -> Result<Ranker, SearchError> {
+        let ranker = match &self.ranker {
+            Some(name) => {
+                let ranker = name.parse::<Ranker>().map_err(SearchError::Request)?;
+
+                if std::mem::discriminant(&ranker) == std::mem::discriminant(&default) {
+                    default
+                } else {
+                    ranker
+                }
+            }
+            None => default,
+        };
+
+        Ok(ranker.tune(self.k1, self.b))
+    }
+
+    fn boosts(&self, default: FieldBoosts) -> FieldBoosts {
+        let mut boosts = default;
+        for (field, boost) in self.boosts.iter().flatten() {
+            boosts.set(*field, *boost);
+        }
+        boosts
+    }
+}
 
 pub struct Serve {
     req: Request,
@@ -17,70 +178,102 @@ impl Serve {
         Self { req }
     }
 
+    fn respond_json(self, status_code: u16, data: &impl Serialize) -> io::Result<()> {
+        let response_data = serde_json::to_string(data)?;
+
+        let header = Header::from_bytes(&b"Content-Type"[..], &b"application/json"[..]).unwrap();
+        let response = Response::from_string(response_data)
+            .with_status_code(status_code)
+            .with_header(header);
+        self.req.respond(response)
+    }
+
     //  Possible Errors:
     //          Read: Interrupted(Non Utf8)
-    //          Empty Query: InvalidInput
-    fn handle_post_method(mut self, json_path: impl AsRef<Path>) -> io::Result<String> {
+    //  Errors of the search itself are sent back to the client as JSON
+    fn handle_post_method(mut self, engine: &Engine) -> io::Result<()> {
         let mut body_data = String::new();
         self.req.as_reader().read_to_string(&mut body_data)?;
 
-        let body_len = body_data.len();
-        let body_data = body_data[1..(body_len - 1)].to_string();
-
-        // If User Input is Empty
-        if body_data.is_empty() {
-            return Err(Error::new(
-                ErrorKind::InvalidInput,
-                "Provide a valid query!",
-            ));
+        match engine.search(&body_data) {
+            Ok(response) => self.respond_json(200, &response),
+            Err(err) => self.respond_json(err.status_code(), &err.to_response()),
         }
+    }
 
-        let lexem = body_data.chars().collect::<Vec<_>>();
-        let lexer = Lexer::new(&lexem);
-
-        let vec_post_data = lexer.collect::<Vec<_>>();
-        let vec = tf(vec_post_data, json_path);
+    // GET /api/complete?prefix=<prefix>&limit=<limit>
+    fn handle_complete(self, query_string: &str, engine: &Engine) -> io::Result<()> {
+        let prefix = normalize(&query_parameter(query_string, "prefix").unwrap_or_default());
+        let limit = query_parameter(query_string, "limit")
+            .and_then(|limit| limit.parse().ok())
+            .unwrap_or(DEFAULT_COMPLETIONS)
+            .min(MAX_COMPLETIONS);
 
-        let response_data = serde_json::to_string(&vec).unwrap();
+        let completions = if prefix.is_empty() {
+            Vec::new()
+        } else {
+            engine
+                .completions
+                .complete(&prefix, limit)
+                .into_iter()
+                .map(|(term, frequency)| Completion { term, frequency })
+                .collect()
+        };
 
-        let header = Header::from_bytes(&b"Content-Type"[..], &b"application/json"[..]).unwrap();
-        let response = Response::from_string(response_data).with_header(header);
-        self.req.respond(response).unwrap();
-        Ok(body_data)
+        self.respond_json(
+            200,
+            &CompleteResponse {
+                prefix,
+                completions,
+            },
+        )
     }
 
     //  Possible Errors ->
     //      File Open: NotFound, Permission Denied, AlreadyExists, InvalidInput
     //      Read: Interrupted(Non Utf8)
-    pub fn handle_connection(self, json_path: impl AsRef<Path>) -> io::Result<()> {
-        match self.req.method() {
-            Method::Get => {
-                let (status_code, filename) = if self.req.url() == "/" {
-                    (200, "index.html")
-                } else {
-                    (404, "404.html")
-                };
+    fn handle_page(self, path: &str) -> io::Result<()> {
+        let (status_code, filename) = if path == "/" {
+            (200, "index.html")
+        } else {
+            (404, "404.html")
+        };
+
+        let filename = format!("{WEB_FILE_DIR}/{filename}");
 
-                let filename = format!("{WEB_FILE_DIR}/{filename}");
+        let contents = fs::read_to_string(filename)?;
+        let readable_stream = contents.as_bytes();
+        let content_length = contents.len();
 
-                let contents = fs::read_to_string(filename)?;
-                let readable_stream = contents.as_bytes();
-                let content_length = contents.len();
+        let header = Header::from_bytes(&b"Content-Type"[..], &b"text/html"[..]).unwrap();
 
-                let header = Header::from_bytes(&b"Content-Type"[..], &b"text/html"[..]).unwrap();
+        let response = Response::new(
+            StatusCode(status_code),
+            vec![header],
+            readable_stream,
+            Some(content_length),
+            None,
+        );
+
+        self.req.respond(response)
+    }
 
-                let response = Response::new(
-                    StatusCode(status_code),
-                    vec![header],
-                    readable_stream,
-                    Some(content_length),
-                    None,
-                );
+    //  Possible Errors ->
+    //      File Open: NotFound, Permission Denied, AlreadyExists, InvalidInput
+    //      Read: Interrupted(Non Utf8)
+    pub fn handle_connection(self, engine: &Engine) -> io::Result<()> {
+        match self.req.method() {
+            Method::Get => {
+                let url = self.req.url().to_string();
+                let (path, query_string) = url.split_once('?').unwrap_or((&url, ""));
 
-                self.req.respond(response).unwrap();
+                match path {
+                    "/api/complete" => self.handle_complete(query_string, engine)?,
+                    _ => self.handle_page(path)?,
+                }
             }
             Method::Post => {
-                self.handle_post_method(json_path).unwrap();
+                self.handle_post_method(engine).unwrap();
             }
             _ => {
                 unimplemented!();
@@ -91,36 +284,212 @@ impl Serve {
     }
 }
 
-fn tf(post_data: Vec<String>, json_path: impl AsRef<Path>) -> Vec<(PathBuf, usize)> {
-    type TermFreq = HashMap<String, usize>;
-    type TermFreqIndex = HashMap<PathBuf, TermFreq>;
-    type TermFreqPath = HashMap<PathBuf, usize>;
+// State shared by every request, built once when the server starts
+pub struct Engine {
+    index: Index,
+    // Corpus frequency of every indexed term
+    vocabulary: HashMap<String, usize>,
+    // Word most often written for every term the analyzer changed, like "handling" for "handl"
+    surfaces: HashMap<String, String>,
+    // Completions are words as they are written, never stems
+    completions: Trie,
+    analyzers: Analyzers,
+    scoring: Scoring,
+}
 
-    let file = std::fs::File::open(json_path).unwrap();
+impl Engine {
+    //  Possible Errors ->
+    //      File Open: NotFound, Permission Denied
+    //      Deserialize: InvalidData
+    pub fn new(json_path: impl AsRef<Path>, scoring: Scoring) -> io::Result<Self> {
+        let index = Index::load(json_path)?;
+        let analyzers = Analyzers::new(&index.settings);
+        Ok(Self::with_analyzers(index, analyzers, scoring))
+    }
 
-    let u: TermFreqIndex = serde_json::from_reader(file).unwrap();
+    // For an index built with analyzers registered on top of its settings, queries need them too
+    pub fn with_analyzers(index: Index, analyzers: Analyzers, scoring: Scoring) -> Self {
+        let vocabulary = index.vocabulary();
+        let surfaces = index.surfaces();
 
-    let vec_term = u.into_iter().collect::<Vec<_>>();
+        let mut written = HashMap::new();
+        for (term, frequency) in &vocabulary {
+            let word = surfaces.get(term).unwrap_or(term);
+            *written.entry(word.as_str()).or_insert(0) += frequency;
+        }
+        let completions = written.into_iter().collect();
 
-    let mut documents = TermFreqPath::new();
-    for (path, termfreq) in vec_term {
-        let iter = post_data.iter();
-        let mut count = 0;
+        Self {
+            index,
+            vocabulary,
+            surfaces,
+            completions,
+            analyzers,
+            scoring,
+        }
+    }
 
-        for word in iter {
-            if termfreq.contains_key(word.as_str()) {
-                count += termfreq.get(word.as_str()).unwrap();
+    fn search(&self, body_data: &str) -> Result<SearchResponse, SearchError> {
+        let request = SearchRequest::from_body(body_data)?;
+        let scoring = Scoring {
+            ranker: request.ranker(self.scoring.ranker)?,
+            boosts: request.boosts(self.scoring.boosts),
+            proximity: request.proximity.unwrap_or(self.scoring.proximity),
+        };
+        let limit = request.limit.unwrap_or(DEFAULT_LIMIT);
+        let offset = request.offset.unwrap_or(0);
+        let mut query =
+            Query::parse(&request.query, &self.analyzers.query()).map_err(SearchError::Parse)?;
+
+        let mut did_you_mean = None;
+        let mut warnings = Vec::new();
+        if request.fuzzy.unwrap_or(true) {
+            let corrections = fuzzy::corrections(&query, &self.vocabulary);
+
+            if !corrections.is_empty() {
+                let spans = term_spans(&request.query, &self.analyzers.query());
+                // Candidates as they are written in the documents, never stems
+                let suggestions = corrections
+                    .iter()
+                    .map(|correction| Correction {
+                        term: correction.term.clone(),
+                        candidates: correction
+                            .candidates
+                            .iter()
+                            .map(|candidate| self.surface(candidate).to_string())
+                            .collect(),
+                    })
+                    .collect::<Vec<_>>();
+
+                for correction in &suggestions {
+                    // The term as the user wrote it
+                    let written = spans
+                        .iter()
+                        .find(|(term, _)| *term == correction.term)
+                        .map_or(correction.term.as_str(), |(_, span)| {
+                            &request.query[span.clone()]
+                        });
+                    warnings.push(format!(
+                        "{:?} is not in the index, searched for {} instead",
+                        written,
+                        correction
+                            .candidates
+                            .iter()
+                            .map(|candidate| format!("{candidate:?}"))
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    ));
+                }
+
+                did_you_mean = Some(fuzzy::did_you_mean(&request.query, &spans, &suggestions));
+                query = fuzzy::expand(query, &corrections);
             }
         }
 
-        if count != 0 {
-            documents.insert(path, count);
-        }
+        let documents = scoring.rank(&query, &self.index);
+        let total = documents.len();
+
+        let with_snippets = request.snippets.unwrap_or(true);
+        let results = top_k(documents, offset.saturating_add(limit))
+            .into_iter()
+            .skip(offset)
+            .map(|(path, score)| {
+                let snippets = if with_snippets {
+                    self.snippets(&path, &query)
+                } else {
+                    Vec::new()
+                };
+
+                SearchResult {
+                    path,
+                    score,
+                    snippets,
+                }
+            })
+            .collect();
+
+        Ok(SearchResponse {
+            total,
+            offset,
+            limit,
+            results,
+            did_you_mean,
+            warnings,
+        })
     }
 
-    let mut documents_vec = documents.into_iter().take(20).collect::<Vec<_>>();
-    documents_vec.sort_by_key(|(_, c)| *c);
-    documents_vec.reverse();
+    fn surface<'t>(&'t self, term: &'t str) -> &'t str {
+        self.surfaces.get(term).map_or(term, String::as_str)
+    }
+
+    // The file is extracted again, a file that cannot be read anymore has no snippets
+    fn snippets(&self, path: &Path, query: &Query) -> Vec<Snippet> {
+        let Some(document) = self.index.documents.get(path) else {
+            return Vec::new();
+        };
+        // A chapter is read from its book
+        let (file_path, chapter) = match split_chapter(path) {
+            Some((book, chapter)) => (book, Some(chapter)),
+            None => (path.to_path_buf(), None),
+        };
+        let Some(Ok(content)) = reader_for(&file_path).map(|reader| reader(&file_path)) else {
+            return Vec::new();
+        };
+        let content = match chapter {
+            Some(chapter) => match content
+                .into_chapters()
+                .into_iter()
+                .find(|(name, _)| *name == chapter)
+            {
+                Some((_, content)) => content,
+                None => return Vec::new(),
+            },
+            None => content,
+        };
+
+        let matches = query
+            .positive_clauses()
+            .into_iter()
+            .flat_map(|clause| {
+                clause
+                    .occurrences(document)
+                    .into_iter()
+                    .map(|start| start..start + clause.len())
+            })
+            .collect::<Vec<_>>();
+
+        snippets(&content, self.analyzers.for_path(&file_path), &matches)
+    }
+}
+
+// Value of a parameter in a URL query string, percent-decoded
+fn query_parameter(query_string: &str, name: &str) -> Option<String> {
+    let value = query_string
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(key, _)| *key == name)?
+        .1;
+
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => decoded.push(b' '),
+            b'%' if i + 2 < bytes.len() => {
+                let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).unwrap_or_default();
+                match u8::from_str_radix(hex, 16) {
+                    Ok(byte) => {
+                        decoded.push(byte);
+                        i += 2;
+                    }
+                    Err(_) => decoded.push(b'%'),
+                }
+            }
+            byte => decoded.push(byte),
+        }
+        i += 1;
+    }
 
-    documents_vec
+    String::from_utf8(decoded).ok()
 }
@@ -0,0 +1,119 @@
+use crate::analyzer::Analyzer;
+use crate::file_types::{Content, Location};
+use crate::lexical_analysis::tokenize_content;
+use serde::Serialize;
+use std::ops::Range;
+
+// Tokens shown before the first match of a snippet
+const CONTEXT_TOKENS: usize = 8;
+const SNIPPET_TOKENS: usize = 24;
+const MAX_SNIPPETS: usize = 3;
+
+#[derive(Serialize)]
+pub struct Snippet {
+    pub text: String,
+    // Character ranges of the matches inside `text`
+    pub highlights: Vec<(usize, usize)>,
+    // Page or sheet of the snippet, as in `"page": 2`
+    #[serde(flatten)]
+    pub location: Option<Location>,
+}
+
+// `matches` are token position ranges, as stored in the index, of the query clauses in the document
+pub fn snippets(
+    content: &Content,
+    analyzer: &dyn Analyzer,
+    matches: &[Range<usize>],
+) -> Vec<Snippet> {
+    let tokens = tokenize_content(content, analyzer)
+        .filter(|token| !token.part)
+        .collect::<Vec<_>>();
+
+    // A file changed since it was indexed can have matches past its end
+    let mut matches = matches
+        .iter()
+        .filter(|m| m.start < m.end && m.end <= tokens.len())
+        .filter(|m| tokens[m.start].section == tokens[m.end - 1].section)
+        .cloned()
+        .collect::<Vec<_>>();
+    matches.sort_by_key(|m| (m.start, m.end));
+
+    let mut snippets = Vec::new();
+    let mut i = 0;
+    while i < matches.len() && snippets.len() < MAX_SNIPPETS {
+        let first = &matches[i];
+        let section = tokens[first.start].section;
+
+        // Snippets never run into another section
+        let mut section_start = first.start;
+        while section_start > 0 && tokens[section_start - 1].section == section {
+            section_start -= 1;
+        }
+        let mut section_end = first.end;
+        while section_end < tokens.len() && tokens[section_end].section == section {
+            section_end += 1;
+        }
+
+        let start = first
+            .start
+            .saturating_sub(CONTEXT_TOKENS)
+            .max(section_start);
+        let end = (start + SNIPPET_TOKENS).max(first.end).min(section_end);
+
+        let text_start = tokens[start].span.start;
+        let text_end = tokens[end - 1].span.end;
+        let text = &content.sections()[section].1[text_start..text_end];
+        // Highlights are sent as character offsets, which is how the page slices the text
+        let chars_before = |byte: usize| text[..byte - text_start].chars().count();
+
+        let mut highlights: Vec<(usize, usize)> = Vec::new();
+        while i < matches.len() && matches[i].end <= end {
+            let highlight_start = chars_before(tokens[matches[i].start].span.start);
+            let highlight_end = chars_before(tokens[matches[i].end - 1].span.end);
+
+            // Overlapping matches, like a term inside a phrase, are highlighted once
+            match highlights.last_mut() {
+                Some(last) if highlight_start < last.1 => last.1 = last.1.max(highlight_end),
+                _ => highlights.push((highlight_start, highlight_end)),
+            }
+            i += 1;
+        }
+
+        snippets.push(Snippet {
+            text: text.to_string(),
+            highlights,
+            location: content.location_of(section).cloned(),
+        });
+    }
+
+    snippets
+}
+
+#[cfg(test)]
+mod tests {
+    use super::snippets;
+    use crate::file_types::{Content, Field};
+    use crate::lexical_analysis::AnalysisSettings;
+
+    #[test]
+    fn highlights_inside_snippet() {
+        let mut content = Content::new();
+        content.push(Field::Title, "Error handling");
+        content.push(Field::Body, "Read the file, handle the error and retry");
+
+        // "Error" in the title, "the error" in the body
+        let snippets = snippets(
+            &content,
+            &AnalysisSettings::default().analyzer(),
+            &[0..1, 7..9],
+        );
+
+        assert_eq!(snippets.len(), 2);
+        assert_eq!(snippets[0].text, "Error handling");
+        assert_eq!(snippets[0].highlights, vec![(0, 5)]);
+
+        let body = &snippets[1];
+        let (start, end) = body.highlights[0];
+        assert_eq!(&body.text[start..end], "the error");
+    }
+}
@@ -0,0 +1,98 @@
+use crate::lexical_analysis::normalize;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::fs;
+use std::io::Result as IOResult;
+use std::path::Path;
+use std::str::FromStr;
+
+// Sorted, so a term can be looked up with a binary search
+#[rustfmt::skip]
+const ENGLISH: &[&str] = &[
+    "a", "about", "above", "after", "again", "against", "all", "am", "an", "and", "any", "are",
+    "as", "at", "be", "because", "been", "before", "being", "below", "between", "both", "but",
+    "by", "can", "could", "did", "do", "does", "doing", "down", "during", "each", "few", "for",
+    "from", "further", "had", "has", "have", "having", "he", "her", "here", "hers", "herself",
+    "him", "himself", "his", "how", "i", "if", "in", "into", "is", "it", "its", "itself", "just",
+    "me", "more", "most", "my", "myself", "no", "nor", "not", "now", "of", "off", "on", "once",
+    "only", "or", "other", "our", "ours", "ourselves", "out", "over", "own", "same", "she",
+    "should", "so", "some", "such", "than", "that", "the", "their", "theirs", "them",
+    "themselves", "then", "there", "these", "they", "this", "those", "through", "to", "too",
+    "under", "until", "up", "very", "was", "we", "were", "what", "when", "where", "which",
+    "while", "who", "whom", "why", "will", "with", "would", "you", "your", "yours", "yourself",
+    "yourselves",
+];
+
+// Terms left out of the index, and out of the queries searched in it
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StopWords {
+    // Tokens without a letter or a digit, like "," or "["
+    pub punctuation: bool,
+    pub english: bool,
+    // Normalized words of a user supplied file
+    pub custom: BTreeSet<String>,
+}
+
+impl StopWords {
+    // `term` is expected to be normalized already
+    pub fn contains(&self, term: &str) -> bool {
+        (self.punctuation && !term.chars().any(char::is_alphanumeric))
+            || (self.english && ENGLISH.binary_search(&term).is_ok())
+            || self.custom.contains(term)
+    }
+
+    //  Possible Errors ->
+    //      File Open: NotFound, Permission Denied
+    //      Read: InvalidData(Non Utf8)
+    //  Words are separated by whitespace, lines starting with '#' are comments
+    pub fn read_file(&mut self, file_path: impl AsRef<Path>) -> IOResult<()> {
+        let source = fs::read_to_string(file_path)?;
+
+        for line in source.lines() {
+            if line.trim_start().starts_with('#') {
+                continue;
+            }
+
+            self.custom.extend(line.split_whitespace().map(normalize));
+        }
+
+        Ok(())
+    }
+}
+
+// Built-in lists, as in "punctuation,english"
+impl FromStr for StopWords {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut stop_words = StopWords::default();
+
+        for list in s.split(',') {
+            match list.trim() {
+                "punctuation" => stop_words.punctuation = true,
+                "english" => stop_words.english = true,
+                list => return Err(format!("{list:?} is not a built-in stop word list")),
+            }
+        }
+
+        Ok(stop_words)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{StopWords, ENGLISH};
+
+    #[test]
+    fn built_in_lists() {
+        assert!(ENGLISH.windows(2).all(|pair| pair[0] < pair[1]));
+
+        let stop_words = "punctuation,english".parse::<StopWords>().unwrap();
+        assert!(stop_words.contains(","));
+        assert!(stop_words.contains("["));
+        assert!(stop_words.contains("the"));
+        assert!(!stop_words.contains("rust"));
+        assert!(!stop_words.contains("20"));
+    }
+}
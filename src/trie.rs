@@ -0,0 +1,156 @@
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BinaryHeap};
+
+#[derive(Default)]
+struct Node {
+    children: BTreeMap<char, Node>,
+    // Corpus frequency of the word ending here, zero when no word does
+    frequency: usize,
+    // Highest frequency of any word below this node
+    best: usize,
+}
+
+#[derive(Default)]
+pub struct Trie {
+    root: Node,
+}
+
+impl Trie {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, word: &str, frequency: usize) {
+        let mut node = &mut self.root;
+        node.best = node.best.max(frequency);
+
+        for c in word.chars() {
+            node = node.children.entry(c).or_default();
+            node.best = node.best.max(frequency);
+        }
+
+        node.frequency += frequency;
+        node.best = node.best.max(node.frequency);
+    }
+
+    fn find(&self, prefix: &str) -> Option<&Node> {
+        let mut node = &self.root;
+        for c in prefix.chars() {
+            node = node.children.get(&c)?;
+        }
+        Some(node)
+    }
+
+    // Most frequent words starting with `prefix`, only the branches that can still win are visited
+    pub fn complete(&self, prefix: &str, limit: usize) -> Vec<(String, usize)> {
+        let Some(start) = self.find(prefix) else {
+            return Vec::new();
+        };
+
+        // A node is queued with the best frequency below it, and again as a word once it is reached
+        let mut heap = BinaryHeap::new();
+        heap.push(Candidate {
+            priority: start.best,
+            word: prefix.to_string(),
+            node: start,
+            complete: false,
+        });
+
+        let mut completions = Vec::new();
+        while let Some(candidate) = heap.pop() {
+            if completions.len() >= limit {
+                break;
+            }
+
+            if candidate.complete {
+                completions.push((candidate.word, candidate.priority));
+                continue;
+            }
+
+            let node = candidate.node;
+            if node.frequency > 0 {
+                heap.push(Candidate {
+                    priority: node.frequency,
+                    word: candidate.word.clone(),
+                    node,
+                    complete: true,
+                });
+            }
+
+            for (c, child) in &node.children {
+                let mut word = candidate.word.clone();
+                word.push(*c);
+                heap.push(Candidate {
+                    priority: child.best,
+                    word,
+                    node: child,
+                    complete: false,
+                });
+            }
+        }
+
+        completions
+    }
+}
+
+impl<'w> FromIterator<(&'w str, usize)> for Trie {
+    fn from_iter<I: IntoIterator<Item = (&'w str, usize)>>(iter: I) -> Self {
+        let mut trie = Trie::new();
+        for (word, frequency) in iter {
+            trie.insert(word, frequency);
+        }
+        trie
+    }
+}
+
+struct Candidate<'t> {
+    priority: usize,
+    word: String,
+    node: &'t Node,
+    // Whole words go before branches of the same priority
+    complete: bool,
+}
+
+impl PartialEq for Candidate<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Candidate<'_> {}
+
+impl PartialOrd for Candidate<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+// Equal priorities are ordered alphabetically
+impl Ord for Candidate<'_> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.priority
+            .cmp(&other.priority)
+            .then(self.complete.cmp(&other.complete))
+            .then_with(|| other.word.cmp(&self.word))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Trie;
+
+    #[test]
+    fn completions_by_frequency() {
+        let trie = [("rust", 10), ("rustacean", 3), ("rusty", 7), ("ruby", 20), ("go", 50)]
+            .into_iter()
+            .collect::<Trie>();
+
+        assert_eq!(
+            trie.complete("rus", 2),
+            vec![(String::from("rust"), 10), (String::from("rusty"), 7)]
+        );
+        assert_eq!(trie.complete("ru", 1), vec![(String::from("ruby"), 20)]);
+        assert_eq!(trie.complete("py", 5), vec![]);
+        assert_eq!(trie.complete("go", usize::MAX), vec![(String::from("go"), 50)]);
+    }
+}